@@ -1,34 +0,0 @@
-/*
-    1 Vers le bas
-    2 Fin de ligne
-    3 vers la droite
-    4 Vers le bas et la droit
-*/
-
-pub struct Mages;
-
-impl Mages {
-    pub fn mages(name: String) -> Vec<Vec<u8>> {
-        if name == "Map1" {
-            return  [
-                [4, 4, 3, 3, 2, 4, 3, 4, 3, 3, 1],
-                [1, 1, 1, 3, 3, 2, 1, 1, 3, 1, 1],
-                [1, 2, 4, 3, 3, 3, 2, 1, 3, 1, 1],
-                [4, 3, 3, 2, 3, 3, 1, 2, 1, 3, 1],
-                [1, 4, 3, 2, 4, 2, 4, 3, 3, 1, 1],
-                [4, 2, 4, 3, 4, 3, 2, 4, 2, 1, 1],
-                [1, 3, 2, 1, 1, 4, 3, 3, 1, 1, 1],
-                [1, 3, 4, 1, 1, 1, 4, 2, 1, 2, 1],
-                [1, 1, 2, 1, 4, 2, 1, 1, 4, 3, 1],
-                [1, 3, 3, 1, 2, 4, 2, 1, 2, 1, 1],
-                [3, 3, 3, 3, 3, 2, 3, 3, 3, 3, 2]
-            ];
-        } else if name == "Map2" {
-            return  [
-                [],
-            ];
-        }
-        []
-    }
-}
-
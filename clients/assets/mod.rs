@@ -1 +0,0 @@
-pub mod mages;
\ No newline at end of file
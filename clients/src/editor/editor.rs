@@ -0,0 +1,279 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use store::{ load_maze, maze_issues, save_maze, CellWalls, Maze, MazeParams };
+
+const TILE: f32 = 48.0;
+const WALL: f32 = 6.0;
+/// Clicks closer than this (in cells) to a side toggle its wall
+const EDGE_MARGIN: f32 = 0.3;
+
+#[derive(Resource)]
+pub struct EditorState {
+    pub maze: Maze,
+    pub path: PathBuf,
+    pub message: String,
+    pub dirty: bool,
+    /// The file at `path` exists but couldn't be loaded, the next Ctrl+S only warns
+    pub protected: bool,
+}
+
+#[derive(Component)]
+pub struct EditorElement;
+
+#[derive(Component)]
+pub struct EditorText;
+
+/// Runs the level editor instead of the game, the maze is saved to `path`
+pub fn run(path: PathBuf) {
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Custom".to_string());
+    let (maze, message, protected) = match load_maze(&path) {
+        Ok(maze) => (maze, format!("Loaded {}", path.display()), false),
+        Err(_) if matches!(fs::metadata(&path), Err(e) if e.kind() == ErrorKind::NotFound) => {
+            (Maze::empty(name, 10, 10), format!("New map {}", path.display()), false)
+        }
+        Err(e) => {
+            let message = format!("Cannot load {}: {}", path.display(), e);
+            (Maze::empty(name, 10, 10), message, true)
+        }
+    };
+
+    App::new()
+        .insert_resource(EditorState {
+            maze,
+            path,
+            message,
+            dirty: true,
+            protected,
+        })
+        .add_plugins(
+            DefaultPlugins.set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: "IBG - Level editor".into(),
+                    resolution: (1000.0, 800.0).into(),
+                    resizable: true,
+                    ..default()
+                }),
+                ..default()
+            })
+        )
+        .add_systems(Startup, setup_editor)
+        .add_systems(Update, (editor_mouse, editor_keys, draw_editor, display_editor_text).chain())
+        .run();
+}
+
+fn setup_editor(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn(Camera2dBundle::default());
+    commands.spawn((
+        TextBundle::from_section("", TextStyle {
+            font: asset_server.load("fonts/8-bit-hud.ttf"),
+            font_size: 16.0,
+            color: Color::OLIVE,
+        }).with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            top: Val::Px(10.0),
+            ..default()
+        }),
+        EditorText,
+    ));
+}
+
+/// World position of the top-left corner of a cell
+fn cell_origin(maze: &Maze, row: usize, col: usize) -> Vec2 {
+    Vec2::new(
+        ((col as f32) - (maze.cols() as f32) / 2.0) * TILE,
+        ((maze.rows() as f32) / 2.0 - (row as f32)) * TILE
+    )
+}
+
+fn draw_editor(
+    mut commands: Commands,
+    mut state: ResMut<EditorState>,
+    elements: Query<Entity, With<EditorElement>>
+) {
+    if !state.dirty {
+        return;
+    }
+    state.dirty = false;
+    for entity in elements.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let maze = &state.maze;
+    for row in 0..maze.rows() {
+        for col in 0..maze.cols() {
+            let origin = cell_origin(maze, row, col);
+            let center = origin + Vec2::new(TILE / 2.0, -TILE / 2.0);
+            let color = if maze.spawns.contains(&(row, col)) {
+                Color::rgb(0.8, 0.3, 0.2)
+            } else {
+                Color::rgb(0.15, 0.2, 0.15)
+            };
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::splat(TILE - 2.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(center.extend(0.0)),
+                    ..default()
+                },
+                EditorElement,
+            ));
+        }
+    }
+
+    for row in 0..=maze.rows() {
+        for col in 0..=maze.cols() {
+            let origin = cell_origin(maze, row, col);
+            if maze.has_down(row, col) {
+                spawn_wall(&mut commands, origin + Vec2::new(0.0, -TILE / 2.0), Vec2::new(WALL, TILE));
+            }
+            if maze.has_right(row, col) {
+                spawn_wall(&mut commands, origin + Vec2::new(TILE / 2.0, 0.0), Vec2::new(TILE, WALL));
+            }
+        }
+    }
+}
+
+fn spawn_wall(commands: &mut Commands, center: Vec2, size: Vec2) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::GREEN,
+                custom_size: Some(size),
+                ..default()
+            },
+            transform: Transform::from_translation(center.extend(1.0)),
+            ..default()
+        },
+        EditorElement,
+    ));
+}
+
+/// Left click on a side of a cell toggles its wall, right click toggles a spawn point
+fn editor_mouse(
+    mouse: Res<Input<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut state: ResMut<EditorState>
+) {
+    let left = mouse.just_pressed(MouseButton::Left);
+    let right = mouse.just_pressed(MouseButton::Right);
+    if !left && !right {
+        return;
+    }
+    let (camera, camera_transform) = camera_query.single();
+    let cursor = match
+        windows
+            .single()
+            .cursor_position()
+            .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
+    {
+        Some(cursor) => cursor,
+        None => {
+            return;
+        }
+    };
+
+    let maze = &state.maze;
+    let fx = cursor.x / TILE + (maze.cols() as f32) / 2.0;
+    let fy = (maze.rows() as f32) / 2.0 - cursor.y / TILE;
+    if fx < 0.0 || fy < 0.0 || fx >= (maze.cols() as f32) || fy >= (maze.rows() as f32) {
+        return;
+    }
+    let (col, row) = (fx as usize, fy as usize);
+    let (u, v) = (fx.fract(), fy.fract());
+
+    if right {
+        state.maze.toggle_spawn(row, col);
+        state.dirty = true;
+        return;
+    }
+    let (distance, side) = [
+        (v, CellWalls::TOP),
+        (1.0 - v, CellWalls::BOTTOM),
+        (u, CellWalls::LEFT),
+        (1.0 - u, CellWalls::RIGHT),
+    ]
+        .into_iter()
+        .fold((f32::MAX, CellWalls::NONE), |best, side| if side.0 < best.0 { side } else { best });
+    if distance <= EDGE_MARGIN {
+        state.maze.toggle_wall(row, col, side);
+        state.dirty = true;
+    }
+}
+
+/// Ctrl+S saves, V validates, 1-3 load a built-in level, G generates one, N clears the map
+fn editor_keys(keys: Res<Input<KeyCode>>, mut state: ResMut<EditorState>) {
+    let name = state.maze.name.clone();
+    let replace = if keys.just_pressed(KeyCode::Key1) {
+        Some(Maze::level(1))
+    } else if keys.just_pressed(KeyCode::Key2) {
+        Some(Maze::level(2))
+    } else if keys.just_pressed(KeyCode::Key3) {
+        Some(Maze::level(3))
+    } else if keys.just_pressed(KeyCode::G) {
+        let seed = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|time| time.as_millis() as u64)
+            .unwrap_or(0);
+        Some(Maze::generate(&MazeParams::for_level(2, seed)))
+    } else if keys.just_pressed(KeyCode::N) {
+        Some(Maze::empty(name.clone(), 10, 10))
+    } else {
+        None
+    };
+    if let Some(mut maze) = replace {
+        state.message = format!("Started from {}", maze.name);
+        maze.name = name;
+        state.maze = maze;
+        state.dirty = true;
+    }
+
+    if keys.just_pressed(KeyCode::V) {
+        let issues = maze_issues(&state.maze);
+        state.message = if issues.is_empty() {
+            "Map is valid".to_string()
+        } else {
+            issues.join(", ")
+        };
+    }
+
+    let ctrl = keys.pressed(KeyCode::ControlLeft) || keys.pressed(KeyCode::ControlRight);
+    if ctrl && keys.just_pressed(KeyCode::S) {
+        let issues = maze_issues(&state.maze);
+        state.message = if !issues.is_empty() {
+            format!("Not saved: {}", issues.join(", "))
+        } else if state.protected {
+            state.protected = false;
+            format!("{} couldn't be loaded, Ctrl+S again to overwrite it", state.path.display())
+        } else {
+            match save_maze(&state.path, &state.maze) {
+                Ok(()) => format!("Saved to {}", state.path.display()),
+                Err(e) => format!("Not saved: {}", e),
+            }
+        };
+    }
+}
+
+fn display_editor_text(state: Res<EditorState>, mut query: Query<&mut Text, With<EditorText>>) {
+    if !state.is_changed() {
+        return;
+    }
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!(
+            "{}\nLeft click: wall  Right click: spawn\nV: validate  Ctrl+S: save  N: clear\n1-3: built-in level  G: generate\n\n{}",
+            state.maze.name,
+            state.message
+        );
+    }
+}
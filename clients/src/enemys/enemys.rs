@@ -3,7 +3,7 @@ use crate::{EnnemyCreated, GameState};
 use bevy::prelude::*;
 use bevy_rapier3d::dynamics::Velocity;
 use bevy_rapier3d::prelude::{Collider, RigidBody};
-use store::{Position, NBR_OF_LIVES};
+use store::Position;
 
 #[allow(dead_code)]
 #[derive(Component)]
@@ -15,12 +15,13 @@ pub struct Enemy {
 }
 
 impl Enemy {
-    pub fn new(id: u8, name: String, position: Position) -> Self {
+    /// `lives` are the ones the server gave the player
+    pub fn new(id: u8, name: String, position: Position, lives: u8) -> Self {
         Enemy {
             id,
             name,
             position,
-            lives: NBR_OF_LIVES,
+            lives,
         }
     }
 }
@@ -51,7 +52,7 @@ pub fn create_enemys(
 
         let player_entity = commands
             .spawn((
-                Enemy::new(id, format!("Enemy_{}", id), player.position.clone()),
+                Enemy::new(id, player.name.clone(), player.position.clone(), player.lives),
                 SpatialBundle {
                     transform: Transform::from_xyz(
                         player.position.x,
@@ -64,7 +65,7 @@ pub fn create_enemys(
                 Collider::cylinder(1.2, 0.1),
                 Velocity::default(),
             ))
-            .insert(Name::new(format!("Enemy_{}", id)))
+            .insert(Name::new(format!("Enemy_{}", player.name)))
             .with_children(|parent| {
                 // Spawn the transparent cylinder
                 parent.spawn(PbrBundle {
@@ -115,6 +116,7 @@ pub fn update_enemys_position(
     for (mut transform, mut enemy) in query.iter_mut() {
         if let Some(player) = list_player.list.get(&enemy.id) {
             enemy.position = player.position.clone();
+            enemy.lives = player.lives;
             transform.translation =
                 Vec3::new(enemy.position.x, enemy.position.y - 0.2, enemy.position.z);
             transform.rotate_local_y(-player.vision.0 * 0.002);
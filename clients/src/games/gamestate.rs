@@ -31,7 +31,7 @@ pub fn display_timer(
         for mut style in query_style.iter_mut() {
             style.display = Display::DEFAULT;
         }
-    } else if game_state.has_started {
+    } else if game_state.has_started || timer.sec == i32::MAX {
         for mut style in query_style.iter_mut() {
             style.display = Display::None;
         }
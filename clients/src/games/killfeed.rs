@@ -0,0 +1,70 @@
+use bevy::{
+    asset::AssetServer,
+    prelude::{ Color, Commands, Component, Query, Res, ResMut, TextBundle, With },
+    text::{ Text, TextSection, TextStyle },
+    time::Time,
+    ui::{ PositionType, Style, Val },
+    utils::default,
+};
+
+use crate::KillFeed;
+
+#[derive(Component)]
+#[allow(dead_code)]
+pub struct KillFeedText;
+
+/// Ages the kill feed entries, they fade out during their last second
+#[allow(dead_code)]
+pub fn display_killfeed(
+    mut kill_feed: ResMut<KillFeed>,
+    mut query: Query<&mut Text, With<KillFeedText>>,
+    time: Res<Time>
+) {
+    if kill_feed.entries.is_empty() && !kill_feed.is_changed() {
+        return;
+    }
+    for (_, timer) in kill_feed.entries.iter_mut() {
+        timer.tick(time.delta());
+    }
+    kill_feed.entries.retain(|(_, timer)| !timer.finished());
+
+    for mut text in query.iter_mut() {
+        let style = text.sections[0].style.clone();
+        text.sections = kill_feed.entries
+            .iter()
+            .map(|(entry, timer)| {
+                let alpha = timer.remaining_secs().min(1.0);
+                TextSection::new(format!("{}\n", entry), TextStyle {
+                    color: Color::OLIVE.with_a(alpha),
+                    ..style.clone()
+                })
+            })
+            .collect();
+        if text.sections.is_empty() {
+            text.sections.push(TextSection::new("", style));
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub fn setup_killfeed(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_sections([
+                TextSection::new("", TextStyle {
+                    font: asset_server.load("fonts/8-bit-hud.ttf"),
+                    font_size: 18.0,
+                    color: Color::OLIVE,
+                }),
+            ]),
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.0),
+                top: Val::Px(90.0),
+                ..default()
+            },
+            ..default()
+        },
+        KillFeedText,
+    ));
+}
@@ -4,8 +4,6 @@ use bevy::{
     text::{ Text, TextSection, TextStyle },
     ui::{ Display, PositionType, Style, Val },
 };
-use store::NBR_OF_LIVES;
-
 use crate::{ GameState, LifeCounter };
 
 #[derive(Component)]
@@ -30,11 +28,11 @@ pub fn display_lives(
 }
 
 #[allow(dead_code)]
-pub fn setuplives(mut commands: Commands, asset: Res<AssetServer>) {
+pub fn setuplives(mut commands: Commands, asset: Res<AssetServer>, lives: Res<LifeCounter>) {
     commands.spawn((
         TextBundle {
             text: Text::from_sections([
-                TextSection::new(format!("Lives: {}", NBR_OF_LIVES), TextStyle {
+                TextSection::new(format!("Lives: {}", lives.val), TextStyle {
                     font: asset.load("fonts/8-bit-hud.ttf"),
                     font_size: 25.0,
                     color: Color::OLIVE,
@@ -0,0 +1,113 @@
+use bevy::{
+    asset::AssetServer,
+    input::Input,
+    prelude::{ Color, Commands, Component, KeyCode, Query, Res, ResMut, TextBundle, With },
+    text::{ Text, TextSection, TextStyle },
+    ui::{ Display, PositionType, Style, Val },
+    utils::default,
+};
+use bevy_renet::renet::{ DefaultChannel, RenetClient };
+use bincode::serialize;
+use store::GameEvent;
+
+use crate::{ GameState, LobbyInfo, PlayerSpawnInfo, RoomState };
+
+#[derive(Component)]
+#[allow(dead_code)]
+pub struct LobbyText;
+
+/// R toggles ready, the host can press Enter to start without waiting for everyone
+#[allow(dead_code)]
+pub fn lobby_input(
+    keys: Res<Input<KeyCode>>,
+    mut client: ResMut<RenetClient>,
+    lobby: Res<LobbyInfo>,
+    spawn_info: Res<PlayerSpawnInfo>,
+    game_state: Res<GameState>
+) {
+    let own_id = match spawn_info.player_id {
+        Some(id) => id,
+        None => {
+            return;
+        }
+    };
+    if !game_state.is_waiting || !client.is_connected() {
+        return;
+    }
+    if keys.just_pressed(KeyCode::R) {
+        let ready = !lobby.players.get(&own_id).is_some_and(|player| player.ready);
+        client.send_message(
+            DefaultChannel::ReliableOrdered,
+            serialize(&GameEvent::Ready { ready }).unwrap()
+        );
+    }
+    if keys.just_pressed(KeyCode::Return) && lobby.host == Some(own_id) {
+        client.send_message(
+            DefaultChannel::ReliableOrdered,
+            serialize(&GameEvent::ForceStart).unwrap()
+        );
+    }
+}
+
+#[allow(dead_code)]
+pub fn display_lobby(
+    lobby: Res<LobbyInfo>,
+    room: Res<RoomState>,
+    spawn_info: Res<PlayerSpawnInfo>,
+    game_state: Res<GameState>,
+    mut query_text: Query<&mut Text, With<LobbyText>>,
+    mut query_style: Query<&mut Style, With<LobbyText>>
+) {
+    for mut style in query_style.iter_mut() {
+        style.display = if game_state.is_waiting { Display::DEFAULT } else { Display::None };
+    }
+    if !game_state.is_waiting || !lobby.is_changed() {
+        return;
+    }
+
+    let mut players: Vec<_> = lobby.players.iter().collect();
+    players.sort_by_key(|(id, _)| **id);
+    let room_name = room.room.as_ref().map(|room| room.name.as_str()).unwrap_or("LOBBY");
+    let mut text = format!("{} ({}/{} needed)\n", room_name, players.len(), lobby.min_players);
+    for (id, player) in players {
+        text.push_str(
+            &format!(
+                "{} {}{}{}\n",
+                if player.ready { "[READY]" } else { "[     ]" },
+                player.name,
+                if lobby.host == Some(*id) { " (host)" } else { "" },
+                if spawn_info.player_id == Some(*id) { " <" } else { "" }
+            )
+        );
+    }
+    text.push_str("\nR: ready");
+    if lobby.host.is_some() && lobby.host == spawn_info.player_id {
+        text.push_str("  Enter: start now");
+    }
+    for mut lobby_text in query_text.iter_mut() {
+        lobby_text.sections[0].value = text.clone();
+    }
+}
+
+#[allow(dead_code)]
+pub fn setup_lobby(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_sections([
+                TextSection::new("LOBBY", TextStyle {
+                    font: asset_server.load("fonts/8-bit-hud.ttf"),
+                    font_size: 22.0,
+                    color: Color::OLIVE,
+                }),
+            ]),
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(30.0),
+                left: Val::Percent(35.0),
+                ..default()
+            },
+            ..default()
+        },
+        LobbyText,
+    ));
+}
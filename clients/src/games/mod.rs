@@ -1,3 +1,8 @@
 pub mod fps;
 pub mod gamestate;
+pub mod killfeed;
 pub mod live;
+pub mod lobby;
+pub mod respawn;
+pub mod scoreboard;
+pub mod spectator;
@@ -0,0 +1,61 @@
+use bevy::{
+    asset::AssetServer,
+    prelude::{ Color, Commands, Component, Query, Res, ResMut, TextBundle, With },
+    text::{ Text, TextSection, TextStyle },
+    time::Time,
+    ui::{ Display, PositionType, Style, Val },
+    utils::default,
+};
+
+use crate::RespawnTimer;
+
+#[derive(Component)]
+#[allow(dead_code)]
+pub struct RespawnText;
+
+#[allow(dead_code)]
+pub fn display_respawn(
+    mut query_text: Query<&mut Text, With<RespawnText>>,
+    mut query_style: Query<&mut Style, With<RespawnText>>,
+    mut respawn_timer: ResMut<RespawnTimer>,
+    time: Res<Time>
+) {
+    let display = match respawn_timer.timer.as_mut() {
+        Some(timer) => {
+            timer.tick(time.delta());
+            let remaining = timer.remaining_secs().ceil() as u32;
+            for mut text in query_text.iter_mut() {
+                text.sections[0].value = format!("You died! Respawn in {}s", remaining);
+            }
+            Display::DEFAULT
+        }
+        None => Display::None,
+    };
+    for mut style in query_style.iter_mut() {
+        style.display = display;
+    }
+}
+
+#[allow(dead_code)]
+pub fn setup_respawn(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_sections([
+                TextSection::new("", TextStyle {
+                    font: asset_server.load("fonts/8-bit-hud.ttf"),
+                    font_size: 40.0,
+                    color: Color::RED,
+                }),
+            ]),
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(40.0),
+                left: Val::Percent(30.0),
+                display: Display::None,
+                ..default()
+            },
+            ..default()
+        },
+        RespawnText,
+    ));
+}
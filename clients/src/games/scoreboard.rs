@@ -0,0 +1,81 @@
+use bevy::{
+    asset::AssetServer,
+    input::Input,
+    prelude::{ Color, Commands, Component, KeyCode, Query, Res, TextBundle, With },
+    text::{ Text, TextSection, TextStyle },
+    ui::{ BackgroundColor, Display, PositionType, Style, Val },
+    utils::default,
+};
+
+use crate::{ GameState, ListPlayer, PlayerSpawnInfo, Scoreboard };
+
+#[derive(Component)]
+#[allow(dead_code)]
+pub struct ScoreboardText;
+
+/// Shows the scores while Tab is held and between two rounds, best players first
+#[allow(dead_code)]
+pub fn display_scoreboard(
+    keys: Res<Input<KeyCode>>,
+    scoreboard: Res<Scoreboard>,
+    liste_player: Res<ListPlayer>,
+    spawn_info: Res<PlayerSpawnInfo>,
+    game_state: Res<GameState>,
+    mut query_text: Query<&mut Text, With<ScoreboardText>>,
+    mut query_style: Query<&mut Style, With<ScoreboardText>>
+) {
+    let visible = keys.pressed(KeyCode::Tab) || game_state.has_ended;
+    for mut style in query_style.iter_mut() {
+        style.display = if visible { Display::DEFAULT } else { Display::None };
+    }
+    if !visible {
+        return;
+    }
+
+    let mut rows: Vec<_> = scoreboard.scores.iter().collect();
+    rows.sort_by(|(_, a), (_, b)| b.kills.cmp(&a.kills).then(a.deaths.cmp(&b.deaths)));
+    let mut board = format!("{:<12}{:>4}{:>4}{:>5}\n", "PLAYER", "K", "D", "DMG");
+    for (id, score) in rows {
+        let name = liste_player.display_name(*id, spawn_info.player_id);
+        board.push_str(
+            &format!(
+                "{:<12}{:>4}{:>4}{:>5}\n",
+                name.chars().take(11).collect::<String>(),
+                score.kills,
+                score.deaths,
+                score.damage
+            )
+        );
+    }
+    if let Some(next_round) = &scoreboard.next_round {
+        board.push_str(&format!("\n{}\n", next_round));
+    }
+    for mut text in query_text.iter_mut() {
+        text.sections[0].value = board.clone();
+    }
+}
+
+#[allow(dead_code)]
+pub fn setup_scoreboard(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_sections([
+                TextSection::new("", TextStyle {
+                    font: asset_server.load("fonts/8-bit-hud.ttf"),
+                    font_size: 22.0,
+                    color: Color::OLIVE,
+                }),
+            ]),
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(25.0),
+                left: Val::Percent(30.0),
+                display: Display::None,
+                ..default()
+            },
+            background_color: BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.7)),
+            ..default()
+        },
+        ScoreboardText,
+    ));
+}
@@ -0,0 +1,133 @@
+use bevy::{
+    asset::AssetServer,
+    ecs::event::EventReader,
+    input::{ mouse::MouseMotion, Input },
+    math::{ Vec2, Vec3 },
+    prelude::{ Color, Commands, Component, KeyCode, Query, Res, ResMut, TextBundle, Transform, With },
+    text::{ Text, TextSection, TextStyle },
+    time::Time,
+    ui::{ Display, PositionType, Style, Val },
+    utils::default,
+};
+
+use crate::{ player::player::Player, ListPlayer, Spectator };
+
+/// How fast the free camera flies, in world units per second
+const FREE_CAMERA_SPEED: f32 = 8.0;
+/// Distance between the followed player and the camera
+const FOLLOW_DISTANCE: f32 = 2.0;
+
+#[derive(Component)]
+#[allow(dead_code)]
+pub struct SpectatorText;
+
+/// Space follows the next player, F switches between following players and flying freely
+#[allow(dead_code)]
+pub fn spectate(
+    keys: Res<Input<KeyCode>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    mut spectator: ResMut<Spectator>,
+    liste_player: Res<ListPlayer>,
+    mut query: Query<&mut Transform, With<Player>>,
+    time: Res<Time>
+) {
+    if !spectator.active {
+        return;
+    }
+    let mut mouse_delta = Vec2::ZERO;
+    for ev in mouse_motion.read() {
+        mouse_delta += ev.delta;
+    }
+    if keys.just_pressed(KeyCode::F) {
+        spectator.free = !spectator.free;
+    }
+    // the followed player may have died or left
+    let followed = spectator.target
+        .and_then(|id| liste_player.list.get(&id))
+        .is_some_and(|player| player.lives > 0);
+    if keys.just_pressed(KeyCode::Space) || (!spectator.free && !followed) {
+        spectator.next_target(&liste_player.list);
+    }
+
+    for mut transform in query.iter_mut() {
+        transform.rotate_y(-mouse_delta.x * 0.002);
+        if spectator.free {
+            let mut direction = Vec3::ZERO;
+            if keys.pressed(KeyCode::W) {
+                direction += transform.forward();
+            }
+            if keys.pressed(KeyCode::S) {
+                direction += transform.back();
+            }
+            if keys.pressed(KeyCode::A) {
+                direction += transform.left();
+            }
+            if keys.pressed(KeyCode::D) {
+                direction += transform.right();
+            }
+            if keys.pressed(KeyCode::ShiftLeft) {
+                direction += Vec3::Y;
+            }
+            if keys.pressed(KeyCode::ControlLeft) {
+                direction -= Vec3::Y;
+            }
+            transform.translation +=
+                direction.normalize_or_zero() * FREE_CAMERA_SPEED * time.delta_seconds();
+        } else if let Some(player) = spectator.target.and_then(|id| liste_player.list.get(&id)) {
+            // stay behind the player, the mouse turns around it
+            let target = Vec3::new(player.position.x, player.position.y, player.position.z);
+            transform.translation = target + transform.back() * FOLLOW_DISTANCE + Vec3::Y * 0.5;
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub fn display_spectator(
+    spectator: Res<Spectator>,
+    liste_player: Res<ListPlayer>,
+    mut query_text: Query<&mut Text, With<SpectatorText>>,
+    mut query_style: Query<&mut Style, With<SpectatorText>>
+) {
+    for mut style in query_style.iter_mut() {
+        style.display = if spectator.active { Display::DEFAULT } else { Display::None };
+    }
+    if !spectator.active {
+        return;
+    }
+    let text = match (spectator.free, spectator.target) {
+        (false, Some(id)) =>
+            format!(
+                "SPECTATING {}\nSpace: next player  F: free camera",
+                liste_player.display_name(id, None)
+            ),
+        (false, None) => "SPECTATING\nnobody left to follow  F: free camera".to_string(),
+        (true, _) => "FREE CAMERA\nShift/Ctrl: up/down  F: follow players".to_string(),
+    };
+    for mut spectator_text in query_text.iter_mut() {
+        spectator_text.sections[0].value = text.clone();
+    }
+}
+
+#[allow(dead_code)]
+pub fn setup_spectator(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_sections([
+                TextSection::new("", TextStyle {
+                    font: asset_server.load("fonts/8-bit-hud.ttf"),
+                    font_size: 20.0,
+                    color: Color::ORANGE,
+                }),
+            ]),
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Percent(5.0),
+                left: Val::Percent(35.0),
+                display: Display::None,
+                ..default()
+            },
+            ..default()
+        },
+        SpectatorText,
+    ));
+}
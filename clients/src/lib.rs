@@ -2,31 +2,74 @@ use bevy::asset::Assets;
 use bevy::log::{ error, info, warn };
 use bevy::math::Vec3;
 use bevy::pbr::StandardMaterial;
-use bevy::prelude::{ Commands, Mesh, Query, ResMut, Resource, Transform, With };
-use bevy_renet::renet::transport::ClientAuthentication;
+use bevy::prelude::{
+    Commands,
+    DespawnRecursiveExt,
+    Entity,
+    Mesh,
+    Query,
+    ResMut,
+    Resource,
+    Transform,
+    Visibility,
+    With,
+};
+use bevy::ecs::system::SystemParam;
+use bevy::time::{ Timer, TimerMode };
+use bevy_renet::renet::transport::{ ClientAuthentication, ConnectToken };
 use bevy_renet::renet::transport::NetcodeClientTransport;
 use bevy_renet::renet::{ ConnectionConfig, DefaultChannel, RenetClient };
 use bincode::{ deserialize, serialize };
 use std::collections::HashMap;
 use std::{
-    io::{ self, Write },
-    net::{ SocketAddr, UdpSocket },
+    io::{ self, Read, Write },
+    net::{ SocketAddr, TcpStream, UdpSocket },
     process::*,
-    time::SystemTime,
+    time::{ Duration, SystemTime },
+};
+use store::{
+    EndGameReason,
+    GameEvent,
+    LobbyPlayer,
+    Maze,
+    MazeCache,
+    Players,
+    RoomInfo,
+    Score,
+    BUILTIN_LEVELS,
+    GAME_FPS,
+    MIN_PLAYERS,
+    NBR_OF_LIVES,
+    PROTOCOL_ID,
+    TOKEN_PORT_OFFSET,
 };
-use store::{ GameEvent, Players, GAME_FPS, NBR_OF_LIVES, PROTOCOL_ID };
 mod enemys;
 mod games;
 mod player;
 mod player_2d;
 mod playing_field;
+use crate::enemys::enemys::Enemy;
 use crate::player::player::Player;
+use crate::playing_field::playing_field::MazeWall;
 
 #[derive(Default, Resource, Debug)]
 pub struct ListPlayer {
     pub list: HashMap<u8, Players>,
 }
 
+impl ListPlayer {
+    /// Name to show in the HUD for a player, `own_id` being the local player
+    pub fn display_name(&self, id: u8, own_id: Option<u8>) -> String {
+        if own_id == Some(id) {
+            return "You".to_string();
+        }
+        self.list
+            .get(&id)
+            .map(|player| player.name.clone())
+            .unwrap_or_else(|| format!("Player {}", id))
+    }
+}
+
 #[derive(Debug, Default, Resource)]
 pub struct PositionInitial {
     pub x: f32,
@@ -47,8 +90,9 @@ impl LifeCounter {
         Self { val: NBR_OF_LIVES }
     }
 
-    pub fn reduce(&mut self) {
-        self.val -= 1;
+    /// Lives are only ever set from what the server says
+    pub fn set(&mut self, val: u8) {
+        self.val = val;
     }
 }
 #[derive(Debug, Resource)]
@@ -86,12 +130,200 @@ impl GameState {
         self.has_ended = true;
     }
 }
+/// Kills, deaths and damage of every player as last sent by the server
+#[derive(Debug, Default, Resource)]
+pub struct Scoreboard {
+    pub scores: HashMap<u8, Score>,
+    /// When and where the next round is played, once the game is over
+    pub next_round: Option<String>,
+}
+
+/// Last kills announced by the server, newest last. Each one fades out with its timer
+#[derive(Debug, Default, Resource)]
+pub struct KillFeed {
+    pub entries: Vec<(String, Timer)>,
+}
+
+impl KillFeed {
+    pub const MAX_ENTRIES: usize = 5;
+    pub const DURATION: f32 = 5.0;
+
+    pub fn push(&mut self, entry: String) {
+        self.entries.push((entry, Timer::from_seconds(Self::DURATION, TimerMode::Once)));
+        if self.entries.len() > Self::MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+    }
+}
+
+/// Who is waiting in the lobby, as last sent by the server
+#[derive(Debug, Resource)]
+pub struct LobbyInfo {
+    pub players: HashMap<u8, LobbyPlayer>,
+    pub host: Option<u8>,
+    pub min_players: usize,
+}
+
+impl Default for LobbyInfo {
+    fn default() -> Self {
+        Self {
+            players: HashMap::new(),
+            host: None,
+            min_players: MIN_PLAYERS,
+        }
+    }
+}
+
+/// The HUD resources updated from the server messages
+#[derive(SystemParam)]
+pub struct Hud<'w> {
+    pub lives: ResMut<'w, LifeCounter>,
+    pub game_timer: ResMut<'w, GameTimer>,
+    pub respawn_timer: ResMut<'w, RespawnTimer>,
+    pub scoreboard: ResMut<'w, Scoreboard>,
+    pub kill_feed: ResMut<'w, KillFeed>,
+    pub lobby: ResMut<'w, LobbyInfo>,
+    pub room: ResMut<'w, RoomState>,
+    pub spectator: ResMut<'w, Spectator>,
+}
+
+/// Camera of a client that watches the game instead of playing it
+#[derive(Debug, Default, Resource)]
+pub struct Spectator {
+    pub active: bool,
+    /// The player followed by the camera, None flies freely
+    pub target: Option<u8>,
+    pub free: bool,
+}
+
+impl Spectator {
+    /// Follows the next living player after the current one
+    pub fn next_target(&mut self, players: &HashMap<u8, Players>) {
+        let mut ids: Vec<u8> = players
+            .iter()
+            .filter(|(_, player)| player.lives > 0)
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort();
+        self.target = ids
+            .iter()
+            .find(|id| Some(**id) > self.target)
+            .or(ids.first())
+            .copied();
+    }
+}
+
+/// The room asked for before connecting
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum RoomChoice {
+    /// The first open room, or a new one named after the player if none is open
+    #[default]
+    Any,
+    Join(u32),
+    Create(String),
+}
+
+/// The room the client is in, picked from the list sent by the server
+#[derive(Debug, Default, Resource)]
+pub struct RoomState {
+    pub choice: RoomChoice,
+    pub username: String,
+    pub room: Option<RoomInfo>,
+    pub requested: bool,
+}
+
+impl RoomState {
+    pub fn new(choice: RoomChoice, username: &str) -> Self {
+        Self {
+            choice,
+            username: username.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Lives of a player when it spawns in the room
+    pub fn lives(&self) -> u8 {
+        self.room.as_ref().map_or(NBR_OF_LIVES, |room| room.lives)
+    }
+
+    /// The message that asks the server for the chosen room
+    pub fn request(&self, rooms: &[RoomInfo]) -> GameEvent {
+        let create = |name: &str| GameEvent::CreateRoom {
+            name: name.to_string(),
+            lvl: None,
+            mode: None,
+        };
+        match &self.choice {
+            RoomChoice::Any =>
+                rooms
+                    .iter()
+                    .find(|room| room.is_open())
+                    .map(|room| GameEvent::JoinRoom { room_id: room.id })
+                    .unwrap_or_else(|| create(&self.username)),
+            RoomChoice::Join(room_id) => GameEvent::JoinRoom { room_id: *room_id },
+            RoomChoice::Create(name) => create(name),
+        }
+    }
+}
+
+/// Time left before the player comes back in a deathmatch, None while alive
+#[derive(Debug, Default, Resource)]
+pub struct RespawnTimer {
+    pub timer: Option<Timer>,
+}
+
+/// The maze being played, only set once its checksum matches the server's
+#[derive(Debug, Default, Resource)]
+pub struct CurrentMaze {
+    pub maze: Option<Maze>,
+    /// Whether the maze was asked to the server, it must have the `expected` checksum
+    pub requested: bool,
+    pub expected: Option<u64>,
+    /// The built-in levels and the mazes already sent by the server
+    pub cache: MazeCache,
+}
+
 #[derive(Resource)]
 pub struct PlayerSpawnInfo {
     pub player_id: Option<u8>,
     pub position: Option<Vec3>,
 }
 
+/// Builds our copy of the maze with the server's checksum, or asks the server for it
+fn load_maze(
+    client: &mut ResMut<RenetClient>,
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    current_maze: &mut ResMut<CurrentMaze>,
+    lvl: usize,
+    checksum: u64
+) {
+    current_maze.expected = Some(checksum);
+    if let Some(maze) = current_maze.cache.get(checksum).cloned() {
+        info!("level {}: {}", lvl, maze.name);
+        playing_field::playing_field::create_maze(commands, meshes, materials, &maze);
+        current_maze.maze = Some(maze);
+        return;
+    }
+    if lvl <= BUILTIN_LEVELS {
+        // the server may also play generated mazes in place of the built-in levels
+        info!(
+            "level {} isn't ours (checksum {:x}, ours {:x}), downloading it",
+            lvl,
+            checksum,
+            Maze::level(lvl).checksum()
+        );
+    } else {
+        info!("level {} is a custom level, downloading it", lvl);
+    }
+    current_maze.requested = true;
+    client.send_message(
+        DefaultChannel::ReliableOrdered,
+        serialize(&GameEvent::MazeRequest).unwrap()
+    );
+}
+
 pub fn get_input(prompt: &str) -> String {
     print!("{}", prompt);
     io::stdout().flush().unwrap();
@@ -100,24 +332,54 @@ pub fn get_input(prompt: &str) -> String {
     input.trim().to_string()
 }
 
+/// Where a secure server issues its tokens when it wasn't given a `--token-port`
+pub fn default_token_addr(server_addr: &SocketAddr) -> SocketAddr {
+    SocketAddr::new(server_addr.ip(), server_addr.port().wrapping_add(TOKEN_PORT_OFFSET))
+}
+
+/// Asks the token issuer of a secure server for a connect token.
+/// The server answers a status byte, then the token (0) or why it refused one (1)
+pub fn fetch_token(token_addr: &SocketAddr, username: &str) -> Result<ConnectToken, String> {
+    let mut stream = TcpStream::connect_timeout(token_addr, Duration::from_secs(2)).map_err(|e| {
+        format!("no token issuer reachable on {}: {}", token_addr, e)
+    })?;
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    stream.write_all(format!("{}\n", username).as_bytes()).map_err(|e| e.to_string())?;
+    let mut status = [0u8; 1];
+    stream.read_exact(&mut status).map_err(|e| e.to_string())?;
+    if status[0] == 0 {
+        return ConnectToken::read(&mut stream).map_err(|e| e.to_string());
+    }
+    let mut reason = String::new();
+    stream.read_to_string(&mut reason).map_err(|e| e.to_string())?;
+    Err(reason)
+}
+
+/// Connects with the token of a secure server, or unsecurely when there is none
 pub fn setup_networking(
     server_addr: &SocketAddr,
-    username: &str
+    username: &str,
+    token: Option<ConnectToken>
 ) -> (RenetClient, NetcodeClientTransport) {
     let client = RenetClient::new(ConnectionConfig::default());
     let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap();
-    let client_id = current_time.as_millis() as u64;
-
-    let mut user_data = [0u8; 256];
-    let username_len = username.len() as u64;
-    user_data[0..8].copy_from_slice(&username_len.to_le_bytes());
-    user_data[8..8 + username.len()].copy_from_slice(username.as_bytes());
-
-    let authentication = ClientAuthentication::Unsecure {
-        server_addr: *server_addr,
-        client_id,
-        user_data: Some(user_data),
-        protocol_id: PROTOCOL_ID,
+
+    let authentication = match token {
+        Some(connect_token) => ClientAuthentication::Secure { connect_token },
+        None => {
+            let client_id = current_time.as_millis() as u64;
+            let mut user_data = [0u8; 256];
+            let username_len = username.len() as u64;
+            user_data[0..8].copy_from_slice(&username_len.to_le_bytes());
+            user_data[8..8 + username.len()].copy_from_slice(username.as_bytes());
+
+            ClientAuthentication::Unsecure {
+                server_addr: *server_addr,
+                client_id,
+                user_data: Some(user_data),
+                protocol_id: PROTOCOL_ID,
+            }
+        }
     };
 
     let socket = UdpSocket::bind("0.0.0.0:5000").unwrap_or_else(|_| {
@@ -136,9 +398,12 @@ pub fn setup_networking(
 
 pub fn handle_connection(
     mut client: ResMut<RenetClient>,
-    mut lives: ResMut<LifeCounter>,
+    mut hud: Hud,
     mut transport: ResMut<NetcodeClientTransport>,
-    _player_query: Query<&mut Transform, With<Player>>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+    enemy_query: Query<(Entity, &Enemy)>,
+    wall_query: Query<Entity, With<MazeWall>>,
+    mut ennemy_created: ResMut<EnnemyCreated>,
     spawn_info: ResMut<PlayerSpawnInfo>,
     commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -146,7 +411,7 @@ pub fn handle_connection(
     mut location: ResMut<PositionInitial>,
     mut liste_player: ResMut<ListPlayer>,
     mut game_state: ResMut<GameState>,
-    mut game_timer: ResMut<GameTimer>
+    mut current_maze: ResMut<CurrentMaze>
 ) {
     client.update(GAME_FPS);
     if transport.update(GAME_FPS, &mut client).is_err() {
@@ -158,7 +423,7 @@ pub fn handle_connection(
     if client.is_connected() {
         handle_server_messages(
             &mut client,
-            &mut lives,
+            &mut hud,
             commands,
             &mut meshes,
             &mut materials,
@@ -166,7 +431,11 @@ pub fn handle_connection(
             &mut location,
             &mut liste_player,
             &mut game_state,
-            &mut game_timer
+            &mut current_maze,
+            &mut player_query,
+            &enemy_query,
+            &wall_query,
+            &mut ennemy_created
         );
     }
 
@@ -176,7 +445,7 @@ pub fn handle_connection(
 
 pub fn handle_server_messages(
     client: &mut ResMut<RenetClient>,
-    lives: &mut ResMut<LifeCounter>,
+    hud: &mut Hud,
     mut commands: Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
@@ -184,14 +453,19 @@ pub fn handle_server_messages(
     location: &mut ResMut<PositionInitial>,
     liste_player: &mut ResMut<ListPlayer>,
     game_state: &mut ResMut<GameState>,
-    game_timer: &mut ResMut<GameTimer>
+    current_maze: &mut ResMut<CurrentMaze>,
+    player_query: &mut Query<&mut Transform, With<Player>>,
+    enemy_query: &Query<(Entity, &Enemy)>,
+    wall_query: &Query<Entity, With<MazeWall>>,
+    ennemy_created: &mut ResMut<EnnemyCreated>
 ) {
     while let Some(message) = client.receive_message(DefaultChannel::ReliableOrdered) {
         if let Ok(event) = deserialize::<GameEvent>(&message) {
             match event {
-                GameEvent::Spawn { player_id, position, lvl } => {
+                GameEvent::Spawn { player_id, name, position, lvl, checksum } => {
                     info!(
-                        "i am player [{}] located at \"{}°- {}°- {}°\" on level: {}",
+                        "i am {} [{}] located at \"{}°- {}°- {}°\" on level: {}",
+                        name,
                         player_id,
                         position.x,
                         position.y,
@@ -209,25 +483,128 @@ pub fn handle_server_messages(
                     // Stocker les informations de spawn
                     spawn_info.player_id = Some(player_id);
                     spawn_info.position = Some(Vec3::new(position.x, position.y, position.z));
+                    hud.spectator.active = false;
+
+                    // a new round, or a restarted lobby: clear what is left of the previous one
+                    if game_state.has_ended || current_maze.requested || current_maze.maze.is_some() {
+                        for entity in wall_query.iter() {
+                            commands.entity(entity).despawn_recursive();
+                        }
+                        for (entity, _) in enemy_query.iter() {
+                            commands.entity(entity).despawn_recursive();
+                        }
+                        ennemy_created.val = true;
+                        liste_player.list.clear();
+                        current_maze.maze = None;
+                        current_maze.requested = false;
+                        let lives = hud.room.lives();
+                        hud.lives.set(lives);
+                        hud.scoreboard.scores.clear();
+                        hud.scoreboard.next_round = None;
+                        hud.game_timer.sec = i32::MAX;
+                        **game_state = GameState::new();
+                        if let Ok(mut transform) = player_query.get_single_mut() {
+                            transform.translation = Vec3::new(position.x, position.y, position.z);
+                        }
+                    }
 
+                    load_maze(client, &mut commands, meshes, materials, current_maze, lvl, checksum);
+                }
+                GameEvent::Spectate { lvl, checksum, player_list } => {
+                    info!("👀 the game already began, watching level {}", lvl);
+                    load_maze(client, &mut commands, meshes, materials, current_maze, lvl, checksum);
+                    liste_player.list = player_list;
+                    hud.lives.set(0);
+                    hud.spectator.active = true;
+                    game_state.start_game();
+                }
+                GameEvent::MazeData { maze, .. } => {
+                    if !current_maze.requested || current_maze.maze.is_some() {
+                        continue;
+                    }
+                    // the maze must be the one announced by the Spawn, whatever comes with it
+                    if Some(maze.checksum()) != current_maze.expected {
+                        error!(
+                            "❌ desynced level: the server sent a maze with checksum {:x} instead of {:x}",
+                            maze.checksum(),
+                            current_maze.expected.unwrap_or_default()
+                        );
+                        client.disconnect();
+                        exit(1);
+                    }
+                    current_maze.cache.insert(maze.clone());
                     playing_field::playing_field::create_maze(
                         &mut commands,
                         meshes,
                         materials,
-                        format!("Map{}", lvl).as_str()
+                        &maze
                     );
+                    current_maze.maze = Some(maze);
                 }
-                GameEvent::PlayerJoined { player_id, name: _, position: _, .. } => {
-                    // ! implement logic here
-                    info!("[{}] joined the war ", player_id);
+                GameEvent::PositionCorrection { position } => {
+                    warn!("position corrected by the server");
+                    if let Ok(mut transform) = player_query.get_single_mut() {
+                        transform.translation = Vec3::new(position.x, position.y, position.z);
+                    }
+                }
+                GameEvent::RoomList { rooms } => {
+                    for room in &rooms {
+                        info!(
+                            "🏠 [{}] {} - level {} ({}) - {:?} - {}/{} players{}",
+                            room.id,
+                            room.name,
+                            room.lvl,
+                            room.level,
+                            room.mode,
+                            room.players,
+                            room.max_players,
+                            if room.is_open() { "" } else { " - closed" }
+                        );
+                    }
+                    if hud.room.room.is_none() && !hud.room.requested {
+                        hud.room.requested = true;
+                        client.send_message(
+                            DefaultChannel::ReliableOrdered,
+                            serialize(&hud.room.request(&rooms)).unwrap()
+                        );
+                    }
+                }
+                GameEvent::RoomJoined { room } => {
+                    info!("🏠 Joined room {}", room.name);
+                    hud.lives.set(room.lives);
+                    hud.room.room = Some(room);
+                }
+                GameEvent::RoomRefused { reason } => {
+                    if hud.room.choice != RoomChoice::Any {
+                        error!("❌ The server refused your room: {}", reason);
+                        exit(1);
+                    }
+                    // someone took the last place, open a room of our own instead
+                    warn!("{}, creating a new room", reason);
+                    hud.room.choice = RoomChoice::Create(hud.room.username.clone());
+                    client.send_message(
+                        DefaultChannel::ReliableOrdered,
+                        serialize(&hud.room.request(&[])).unwrap()
+                    );
+                }
+                GameEvent::PlayerJoined { player_id, name, .. } => {
+                    info!("{} [{}] joined the war ", name, player_id);
                 }
 
                 GameEvent::PlayerMove { player_list, .. } => {
                     //println!("****************FROM SERVER => {:#?}***************", player_list);
                     liste_player.list = player_list;
                 }
+                GameEvent::Lobby { players, host, min_players, countdown } => {
+                    hud.lobby.players = players;
+                    hud.lobby.host = host;
+                    hud.lobby.min_players = min_players;
+                    if countdown.is_none() {
+                        hud.game_timer.sec = i32::MAX;
+                    }
+                }
                 GameEvent::Timer { duration } => {
-                    game_timer.sec = duration as i32;
+                    hud.game_timer.sec = duration as i32;
                 }
 
                 GameEvent::BeginGame { player_list } => {
@@ -235,41 +612,132 @@ pub fn handle_server_messages(
                     info!("Game has begun with warriors => {:#?}", player_list);
                 }
 
+                GameEvent::NameRejected { reason } => {
+                    error!("❌ The server refused your username: {}", reason);
+                    exit(1);
+                }
+
+                GameEvent::Kicked { reason } => {
+                    error!("❌ The server disconnected you: {}", reason);
+                    exit(1);
+                }
+
+                GameEvent::ServerMessage { text } => {
+                    info!("📢 {}", text);
+                    hud.kill_feed.push(format!("SERVER: {}", text));
+                }
+
                 GameEvent::AccessForbidden => {
                     info!("❌ Oops ! ongoing game...");
                     exit(1);
                 }
 
-                GameEvent::EndGame => {
-                    info!("🥉 i am the winner");
-                    println!("💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣");
-                    println!("💣                                                  💣");
-                    println!("💣          👑 YOU WON ! THE WARRIOR  👑           💣");
-                    println!("💣                                                  💣");
-                    println!("💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣");
+                GameEvent::EndGame { reason } => {
+                    game_state.end_game();
+                    hud.respawn_timer.timer = None;
+                    match reason.winner() {
+                        Some(winner) if spawn_info.player_id == Some(winner) => {
+                            info!("🥉 i am the winner ({:?})", reason);
+                            println!("💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣");
+                            println!("💣                                                  💣");
+                            println!("💣          👑 YOU WON ! THE WARRIOR  👑           💣");
+                            println!("💣                                                  💣");
+                            println!("💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣💣");
+                        }
+                        Some(winner) => info!("🏁 [{}] won the game ({:?})", winner, reason),
+                        None if reason == EndGameReason::Stopped => {
+                            info!("🛑 the game was stopped by an admin");
+                        }
+                        None => info!("🏁 the game ended in a draw ({:?})", reason),
+                    }
+                }
+                GameEvent::NextRound { lvl, level, in_secs } => {
+                    info!("🔁 next round on level {} ({}) in {}s", lvl, level, in_secs);
+                    hud.scoreboard.next_round = Some(
+                        format!("NEXT: LEVEL {} ({}) IN {}S", lvl, level, in_secs)
+                    );
+                }
+
+                GameEvent::Impact { id, lives: remaining, attacker } => {
+                    if spawn_info.player_id == Some(id) {
+                        hud.lives.set(remaining);
+                        info!("💥 hit by [{}], {} lives left", attacker, remaining);
+                    } else if let Some(enemy) = liste_player.list.get_mut(&id) {
+                        enemy.lives = remaining;
+                        info!("💥 [{}] was hit by [{}], {} lives left", id, attacker, remaining);
+                    }
+                }
+
+                GameEvent::Kill { killer, victim, weapon } => {
+                    let killer = liste_player.display_name(killer, spawn_info.player_id);
+                    let victim = liste_player.display_name(victim, spawn_info.player_id);
+                    hud.kill_feed.push(format!("{} [{}] {}", killer, weapon, victim));
                 }
-                GameEvent::Impact { id } => {
-                    lives.reduce();
-                    if lives.val == 0 {
-                        let death_event = GameEvent::Death { player_id: id };
 
-                        client.send_message(
-                            DefaultChannel::ReliableOrdered,
-                            serialize(&death_event).unwrap()
+                GameEvent::Scores { scores } => {
+                    hud.scoreboard.scores = scores;
+                }
+
+                GameEvent::Death { player_id, respawn_in: Some(seconds) } => {
+                    if spawn_info.player_id == Some(player_id) {
+                        hud.lives.set(0);
+                        hud.respawn_timer.timer = Some(
+                            Timer::from_seconds(seconds as f32, TimerMode::Once)
                         );
+                        info!("💀 you died, back in {}s", seconds);
+                    } else {
+                        info!("🔻 [{}] has died", player_id);
+                        if let Some(enemy) = liste_player.list.get_mut(&player_id) {
+                            enemy.lives = 0;
+                        }
+                        for (entity, enemy) in enemy_query.iter() {
+                            if enemy.id == player_id {
+                                commands.entity(entity).insert(Visibility::Hidden);
+                            }
+                        }
+                    }
+                }
+
+                GameEvent::Death { player_id, respawn_in: None } => {
+                    if spawn_info.player_id == Some(player_id) {
+                        hud.lives.set(0);
                         println!("❌❌❌❌❌❌❌❌❌❌❌❌❌❌❌❌❌❌❌❌❌❌");
                         println!("❌                                              ❌");
                         println!("❌       😔 GAME OVER TRY AGAIN WARRIOR 😔     ❌");
                         println!("❌                                              ❌");
                         println!("❌❌❌❌❌❌❌❌❌❌❌❌❌❌❌❌❌❌❌❌❌❌");
-                        client.disconnect();
-                        game_state.end_game();
+                        // watch the others until the next round
+                        hud.spectator.active = true;
+                    } else {
+                        info!("🔻 [{}] has died", player_id);
+                        liste_player.list.remove(&player_id);
+                        for (entity, enemy) in enemy_query.iter() {
+                            if enemy.id == player_id {
+                                commands.entity(entity).despawn_recursive();
+                            }
+                        }
                     }
                 }
 
-                GameEvent::Death { player_id } => {
-                    info!("🔻 [{}] has died", player_id);
-                    // liste_player.list.remove(&player_id);
+                GameEvent::Respawn { player_id, position } => {
+                    if spawn_info.player_id == Some(player_id) {
+                        let lives = hud.room.lives();
+                        hud.lives.set(lives);
+                        hud.respawn_timer.timer = None;
+                        if let Ok(mut transform) = player_query.get_single_mut() {
+                            transform.translation = Vec3::new(position.x, position.y, position.z);
+                        }
+                    } else {
+                        if let Some(enemy) = liste_player.list.get_mut(&player_id) {
+                            enemy.lives = hud.room.lives();
+                            enemy.position = position;
+                        }
+                        for (entity, enemy) in enemy_query.iter() {
+                            if enemy.id == player_id {
+                                commands.entity(entity).insert(Visibility::Inherited);
+                            }
+                        }
+                    }
                 }
 
                 // ! do the same for other events
@@ -2,24 +2,36 @@ use bevy::diagnostic::{ FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin };
 use bevy::prelude::*;
 use bevy_rapier3d::plugin::{ NoUserData, RapierPhysicsPlugin };
 use bevy_renet::{ transport::NetcodeClientPlugin, RenetClientPlugin };
-use games::{ fps::*, gamestate::{ display_timer, setup_timer }, live::* };
+use games::{ fps::*, gamestate::{ display_timer, setup_timer }, killfeed::*, live::*, lobby::*, respawn::*, scoreboard::*, spectator::* };
 use multiplayer_fps::{
+    default_token_addr,
     get_input,
+    fetch_token,
     handle_connection,
     setup_networking,
     Counter,
+    CurrentMaze,
     EnnemyCreated,
     GameState,
     GameTimer,
+    KillFeed,
     LifeCounter,
     ListPlayer,
+    LobbyInfo,
     PlayerSpawnInfo,
     PositionInitial,
+    RespawnTimer,
+    RoomChoice,
+    RoomState,
+    Scoreboard,
+    Spectator,
 };
-use std::{ i32, net::SocketAddr };
+use std::{ i32, net::SocketAddr, path::PathBuf };
+use store::{ maps_dir, validate_name };
 // use bevy::sprite::collide_aabb::collide;
 // use bevy::render::debug::DebugLines;
 // use bevy_gltf::Gltf;
+mod editor;
 mod enemys;
 mod player;
 mod player_2d;
@@ -38,8 +50,18 @@ struct MinimapPlayer;
 #[derive(Component)]
 #[allow(dead_code)]
 struct Crosshair;
-const MAX_USERNAME_LENGTH: usize = 248;
 fn main() {
+    // cargo run -- --editor [map file], saved by default in the maps directory (see maps_dir)
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(|arg| arg.as_str()) == Some("--editor") {
+        let path = args
+            .get(2)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| maps_dir().join("custom.maze"));
+        editor::editor::run(path);
+        return;
+    }
+
     let server_ip = get_input("Enter server IP address: ");
     let server_addr: SocketAddr = match server_ip.parse() {
         Ok(addr) => addr,
@@ -49,18 +71,58 @@ fn main() {
         }
     };
 
-    let username = get_input("Enter your username: ");
-    if username.is_empty() {
-        eprintln!("❌ Please provide a username");
-        return;
-    }
+    let username = match validate_name(&get_input("Enter your username: ")) {
+        Ok(username) => username,
+        Err(e) => {
+            eprintln!("❌ Invalid username: {}", e);
+            return;
+        }
+    };
 
-    if username.len() > MAX_USERNAME_LENGTH {
-        eprintln!("❌ Username is too long (max {} characters)", MAX_USERNAME_LENGTH);
-        return;
-    }
+    let room = get_input("Room to join (Enter for any, a number, or a name to create one): ");
+    let choice = if room.is_empty() {
+        RoomChoice::Any
+    } else if let Ok(room_id) = room.parse() {
+        RoomChoice::Join(room_id)
+    } else {
+        match validate_name(&room) {
+            Ok(name) => RoomChoice::Create(name),
+            Err(e) => {
+                eprintln!("❌ Invalid room name: {}", e);
+                return;
+            }
+        }
+    };
+
+    // secure servers only let in the clients holding one of their tokens
+    let default_token = default_token_addr(&server_addr);
+    let secure = get_input(
+        &format!(
+            "Token issuer of a secure server (Enter for none, y for {}, or an address): ",
+            default_token
+        )
+    );
+    let token_addr = match secure.as_str() {
+        "" => None,
+        "y" | "yes" => Some(default_token),
+        addr =>
+            match addr.parse() {
+                Ok(addr) => Some(addr),
+                Err(_) => {
+                    eprintln!("❌ Invalid token issuer address!");
+                    return;
+                }
+            }
+    };
+    let token = match token_addr.map(|addr| fetch_token(&addr, &username)).transpose() {
+        Ok(token) => token,
+        Err(e) => {
+            eprintln!("❌ Cannot get a connect token: {}", e);
+            return;
+        }
+    };
 
-    let (client, transport) = setup_networking(&server_addr, &username);
+    let (client, transport) = setup_networking(&server_addr, &username, token);
     let position = PositionInitial::default();
     let counter = Counter::default();
     let list_user = ListPlayer::default();
@@ -78,6 +140,13 @@ fn main() {
         .insert_resource(game_state)
         .insert_resource(ennemy_created)
         .insert_resource(life_counter)
+        .insert_resource(CurrentMaze::default())
+        .insert_resource(RespawnTimer::default())
+        .insert_resource(Scoreboard::default())
+        .insert_resource(KillFeed::default())
+        .insert_resource(LobbyInfo::default())
+        .insert_resource(RoomState::new(choice, &username))
+        .insert_resource(Spectator::default())
         .add_plugins(
             DefaultPlugins.set(WindowPlugin {
                 primary_window: Some(Window {
@@ -109,6 +178,11 @@ fn main() {
             setup_timer,
             setupfps,
             setuplives,
+            setup_respawn,
+            setup_scoreboard,
+            setup_killfeed,
+            setup_lobby,
+            setup_spectator,
         ))
         // .add_systems(Startup, setup)
         .add_systems(
@@ -116,9 +190,16 @@ fn main() {
             (
                 fps_display_system,
                 display_lives,
+                display_respawn,
+                display_scoreboard,
+                display_killfeed,
+                display_lobby,
+                lobby_input,
+                display_spectator,
                 display_timer,
                 handle_connection,
                 player::player::move_player,
+                spectate,
                 player::player::grab_mouse,
                 player::fire::fire_projectile,
                 player::fire::update_projectiles,
@@ -1,5 +1,5 @@
 use crate::enemys::enemys::Enemy;
-use crate::GameState;
+use crate::{ GameState, LifeCounter };
 use crate::{ player::player::Player, playing_field::playing_field::Collision };
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
@@ -7,7 +7,7 @@ use bevy::window::Window;
 use bevy::input::mouse::MouseButton;
 use bevy_renet::renet::{ DefaultChannel, RenetClient };
 use bincode::serialize;
-use store::GameEvent;
+use store::{ now_millis, GameEvent, Position };
 
 #[derive(Component)]
 #[allow(dead_code)]
@@ -31,11 +31,14 @@ pub fn fire_projectile(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mouse: Res<Input<MouseButton>>,
+    mut client: ResMut<RenetClient>,
+    game_state: Res<GameState>,
+    lives: Res<LifeCounter>,
     query: Query<(&Transform, &Player)>,
     windows: Query<&Window>,
     camera_query: Query<(&Camera, &GlobalTransform)>
 ) {
-    if mouse.just_pressed(MouseButton::Left) {
+    if mouse.just_pressed(MouseButton::Left) && lives.val > 0 {
         if let Ok((player_transform, _player)) = query.get_single() {
             if let Ok((camera, camera_transform)) = camera_query.get_single() {
                 let window = windows.single();
@@ -46,6 +49,23 @@ pub fn fire_projectile(
                         player_transform.translation + player_transform.forward() * 0.6;
                     let projectile_direction = ray.direction;
 
+                    // the server decides what the shot hits
+                    if client.is_connected() && game_state.has_started && !game_state.has_ended {
+                        let fire_event = GameEvent::Fire {
+                            origin: Position::new(spawn_point.x, spawn_point.y, spawn_point.z),
+                            direction: Position::new(
+                                projectile_direction.x,
+                                projectile_direction.y,
+                                projectile_direction.z
+                            ),
+                            timestamp: now_millis(),
+                        };
+                        client.send_message(
+                            DefaultChannel::ReliableOrdered,
+                            serialize(&fire_event).unwrap()
+                        );
+                    }
+
                     commands.spawn(ProjectileBundle {
                         projectile: Projectile {
                             speed: 100.0,
@@ -115,41 +135,22 @@ fn ray_from_screenspace(
 
 #[allow(dead_code)]
 pub fn handle_projectile_collisions(
-    mut client: ResMut<RenetClient>,
-    game_state: Res<GameState>,
     mut commands: Commands,
     projectile_query: Query<(Entity, &Transform), With<Projectile>>,
-    mut enemy_query: Query<(Entity, &mut Enemy, &Transform)>
+    enemy_query: Query<&Transform, With<Enemy>>
 ) {
     const IMPACT_DISTANCE: f32 = 0.45;
 
+    // Projectiles are only visual, hits are reported by the server as Impact events
     for (projectile_entity, projectile_transform) in projectile_query.iter() {
         let projectile_position = projectile_transform.translation;
-
-        for (enemy_entity, mut enemy, enemy_transform) in enemy_query.iter_mut() {
-            let enemy_position = enemy_transform.translation;
-            let distance = projectile_position.distance(enemy_position);
-
-            if distance < IMPACT_DISTANCE {
-                // Réduire les vies de l'ennemi
-                enemy.lives = enemy.lives.saturating_sub(1);
-                if client.is_connected() && !game_state.has_ended {
-                    let impact_event = GameEvent::Impact { id: enemy.id };
-                    client.send_message(
-                        DefaultChannel::ReliableOrdered,
-                        serialize(&impact_event).unwrap()
-                    );
-                    println!("  💥:::::::::Enemy hit! Lives remaining: {}:::::::::💥", enemy.lives);
-                    if enemy.lives == 0 {
-                        commands.entity(enemy_entity).despawn_recursive();
-                        println!("  💥💥💥💥💥💥💥💥DEADDDDDDDD💥💥💥💥💥💥💥💥💥");
-                    }
-                    // Despawn le projectile
-                    commands.entity(projectile_entity).despawn();
-                }
-
-                break;
-            }
+        let touches_enemy = enemy_query
+            .iter()
+            .any(|enemy_transform| {
+                projectile_position.distance(enemy_transform.translation) < IMPACT_DISTANCE
+            });
+        if touches_enemy {
+            commands.entity(projectile_entity).despawn();
         }
     }
 }
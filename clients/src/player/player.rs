@@ -3,12 +3,12 @@ use bevy::prelude::*;
 use std::collections::HashMap;
 // use crate::playing_field::playing_field::Collision;
 // use bevy::ecs::system::ParamSet;
-use crate::{ Counter, GameState, PositionInitial };
+use crate::{ Counter, GameState, LifeCounter, PositionInitial };
 use bevy_rapier3d::dynamics::{ LockedAxes, Velocity };
 use bevy_rapier3d::prelude::{ Collider, GravityScale, RapierContext, RigidBody };
 use bevy_renet::renet::{ DefaultChannel, RenetClient };
 use bincode::serialize;
-use store::{ GameEvent, Position };
+use store::{ GameEvent, Position, PLAYER_SPEED };
 
 use crate::playing_field::playing_field::{ check_player_collision, Collision };
 // use bevy::sprite::collide_aabb::Collision;
@@ -22,7 +22,6 @@ pub struct Player {
     pub speed: f32,
     pub camera_offset: Vec3,
     pub size: Vec2,
-    pub lives: u8,
 }
 #[derive(Component)]
 #[allow(dead_code)]
@@ -32,20 +31,15 @@ pub struct PlayerCamera;
 #[allow(dead_code)]
 pub struct Weapon;
 impl Player {
-    pub fn new(id: i32, name: String, speed: f32, size: Vec2, lives: u8) -> Self {
+    pub fn new(id: i32, name: String, speed: f32, size: Vec2) -> Self {
         Player {
             id,
             name,
             speed,
             camera_offset: Vec3::new(0.0, 0.4, 0.8),
             size,
-            lives,
         }
     }
-    #[allow(dead_code)]
-    pub fn player_lives(&self) -> u8 {
-        self.lives
-    }
 }
 
 #[allow(dead_code)]
@@ -59,13 +53,16 @@ pub fn move_player(
     collider_query: Query<Entity, (With<Collision>, Without<Player>)>,
     location: ResMut<PositionInitial>,
     mut counter: ResMut<Counter>,
-    game_state: Res<GameState>
+    game_state: Res<GameState>,
+    lives: Res<LifeCounter>,
+    time: Res<Time>
 ) {
     let window = windows.single();
     if window.cursor.grab_mode == bevy::window::CursorGrabMode::None {
         return;
     }
-    if !game_state.has_ended {
+    // dead players wait for the server to respawn them
+    if !game_state.has_ended && lives.val > 0 {
         let mut mouse_delta = Vec2::ZERO;
         for ev in mouse_motion.read() {
             mouse_delta += ev.delta;
@@ -93,7 +90,7 @@ pub fn move_player(
 
             direction = direction.normalize_or_zero();
 
-            let movement = direction * player.speed * 0.016;
+            let movement = direction * player.speed * time.delta_seconds();
 
             // Vérifier la collision avant de déplacer le joueur
 
@@ -167,7 +164,7 @@ pub fn setup_player_and_camera(
     // let player_handle:Handle<Scene> = asset_server.load("armes/Soldier.glb#Scene0");
     let player_entity = commands
         .spawn((
-            Player::new(player_id as i32, "Player".to_string(), 5.0, Vec2::new(0.5, 0.5), 3),
+            Player::new(player_id as i32, "Player".to_string(), PLAYER_SPEED, Vec2::new(0.5, 0.5)),
             SceneBundle {
                 scene: player_handle,
                 transform: Transform::from_xyz(x, y, z).with_scale(Vec3::splat(0.4)),
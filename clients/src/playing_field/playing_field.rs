@@ -7,6 +7,7 @@ use bevy_rapier3d::dynamics::RigidBody;
 use bevy_rapier3d::prelude::Collider;
 
 use bevy_rapier3d::prelude::*; // version bevy_rapier3d = "0.17.0"
+use store::{ Maze, ARENA_SIZE };
 
 #[derive(Bundle)]
 struct CustomBundle {
@@ -29,6 +30,11 @@ impl Default for Collision {
     }
 }
 
+/// Everything built from the maze, on the ground and on the minimap, so that it can
+/// be cleared before the next round
+#[derive(Component)]
+pub struct MazeWall;
+
 #[allow(dead_code)]
 pub struct Fields;
 #[allow(dead_code)]
@@ -39,7 +45,7 @@ impl Fields {
         mut materials: ResMut<Assets<StandardMaterial>>,
     ) {
         // Plane
-        let arena_size = 28.0;
+        let arena_size = ARENA_SIZE;
 
         // Ground
         commands.spawn((
@@ -165,7 +171,6 @@ impl Fields {
                 speed: 3.0,
                 camera_offset: Vec3::new(0.0, 0.2, 0.8),
                 size: Vec2::new(1.0, 1.0),
-                lives: 3,
             });
     }
 }
@@ -175,65 +180,26 @@ pub fn create_maze(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
-    name: &str,
+    maze: &Maze,
 ) {
-    let maze = get_mazes(name);
     let wall_height = 2.0;
     let wall_thickness = 0.5;
-    let cell_size = 2.0;
 
-    for (row_index, row) in maze.iter().enumerate() {
-        for (col_index, &cell) in row.iter().enumerate() {
-            let x = col_index as f32 * cell_size - 9.0;
-            let z = row_index as f32 * cell_size - 9.0;
-
-            match cell {
-                4 => {
-                    // Vers le bas et la droite
-                    spawn_wall(
-                        commands,
-                        meshes,
-                        materials,
-                        Vec3::new(x, wall_height / 2.0, z + cell_size / 2.0),
-                        Vec3::new(wall_thickness, wall_height, cell_size),
-                    );
-                    spawn_wall(
-                        commands,
-                        meshes,
-                        materials,
-                        Vec3::new(x + cell_size / 2.0, wall_height / 2.0, z),
-                        Vec3::new(cell_size, wall_height, wall_thickness),
-                    );
-                    spawn_minimap_wall(commands, x, z, true, true)
-                }
-                3 => {
-                    // Vers la droite
-                    spawn_wall(
-                        commands,
-                        meshes,
-                        materials,
-                        Vec3::new(x + cell_size / 2.0, wall_height / 2.0, z),
-                        Vec3::new(cell_size, wall_height, wall_thickness),
-                    );
-                    spawn_minimap_wall(commands, x, z, false, true)
-                }
-                1 => {
-                    // Vers le bas
-                    spawn_wall(
-                        commands,
-                        meshes,
-                        materials,
-                        Vec3::new(x, wall_height / 2.0, z + cell_size / 2.0),
-                        Vec3::new(wall_thickness, wall_height, cell_size),
-                    );
-                    spawn_minimap_wall(commands, x, z, true, false)
-                }
-                2 => {
-                    // Fin de ligne (pas de mur)
-                }
-                _ => {}
-            }
-        }
+    for segment in maze.segments() {
+        let (x, z) = segment.center();
+        let size = if segment.is_vertical() {
+            Vec3::new(wall_thickness, wall_height, segment.length())
+        } else {
+            Vec3::new(segment.length(), wall_height, wall_thickness)
+        };
+        spawn_wall(commands, meshes, materials, Vec3::new(x, wall_height / 2.0, z), size);
+        spawn_minimap_wall(
+            commands,
+            segment.start.0,
+            segment.start.1,
+            segment.is_vertical(),
+            !segment.is_vertical(),
+        );
     }
 }
 
@@ -257,6 +223,7 @@ fn spawn_wall(
         },
         RigidBody::Fixed,
         Collider::cuboid(size.x * 0.5, size.y * 0.5, size.z * 0.5),
+        MazeWall,
     ));
 }
 
@@ -279,6 +246,7 @@ fn spawn_minimap_wall(commands: &mut Commands, x: f32, z: f32, vertical: bool, h
                 ..default()
             },
             MinimapElement,
+            MazeWall,
         ));
     }
     if horizontal {
@@ -296,56 +264,11 @@ fn spawn_minimap_wall(commands: &mut Commands, x: f32, z: f32, vertical: bool, h
                 ..default()
             },
             MinimapElement,
+            MazeWall,
         ));
     }
 }
 
-fn get_mazes(name: &str) -> Vec<Vec<u8>> {
-    if name == "Map1" {
-        vec![
-            vec![4, 3, 2, 3, 3, 3, 3, 4, 3, 3, 1],
-            vec![1, 1, 1, 3, 3, 2, 1, 1, 3, 1, 1],
-            vec![1, 2, 2, 2, 3, 3, 2, 2, 2, 3, 1],
-            vec![1, 3, 3, 2, 3, 3, 1, 2, 3, 2, 1],
-            vec![1, 1, 3, 2, 3, 2, 3, 2, 3, 1, 2],
-            vec![2, 3, 3, 2, 1, 2, 3, 3, 2, 1, 1],
-            vec![1, 3, 3, 2, 1, 2, 2, 1, 2, 1, 1],
-            vec![1, 2, 3, 1, 1, 2, 3, 2, 1, 2, 1],
-            vec![1, 3, 2, 2, 3, 2, 1, 2, 1, 2, 1],
-            vec![1, 3, 3, 3, 2, 4, 2, 2, 2, 3, 1],
-            vec![3, 3, 3, 3, 3, 3, 3, 3, 2, 3, 2],
-        ]
-    } else if name == "Map2" {
-        vec![
-            vec![4, 3, 2, 3, 3, 4, 3, 4, 3, 3, 1],
-            vec![1, 1, 1, 3, 3, 2, 1, 1, 3, 1, 1],
-            vec![1, 2, 2, 3, 3, 3, 2, 1, 2, 3, 1],
-            vec![1, 3, 3, 2, 3, 3, 1, 2, 3, 2, 1],
-            vec![1, 1, 3, 2, 3, 2, 3, 2, 3, 1, 1],
-            vec![1, 3, 3, 2, 1, 2, 3, 3, 2, 1, 1],
-            vec![1, 3, 3, 2, 1, 2, 2, 1, 1, 1, 1],
-            vec![1, 2, 3, 1, 4, 2, 3, 2, 4, 2, 1],
-            vec![1, 3, 2, 2, 3, 2, 1, 2, 4, 2, 1],
-            vec![1, 3, 3, 3, 2, 4, 2, 2, 2, 3, 1],
-            vec![3, 3, 3, 3, 3, 3, 3, 3, 2, 3, 2],
-        ]
-    } else {
-        vec![
-            vec![4, 3, 3, 3, 3, 4, 3, 4, 3, 3, 1],
-            vec![1, 1, 1, 3, 3, 2, 1, 1, 3, 1, 1],
-            vec![1, 2, 4, 3, 4, 3, 2, 1, 3, 3, 1],
-            vec![1, 3, 3, 2, 3, 3, 1, 2, 3, 3, 1],
-            vec![1, 1, 3, 2, 4, 2, 3, 2, 3, 1, 1],
-            vec![1, 3, 3, 2, 4, 3, 3, 3, 2, 1, 1],
-            vec![1, 3, 3, 2, 1, 4, 2, 4, 1, 1, 1],
-            vec![1, 4, 3, 1, 4, 2, 3, 2, 4, 2, 1],
-            vec![1, 3, 2, 4, 3, 2, 1, 4, 4, 2, 1],
-            vec![1, 3, 3, 3, 2, 4, 2, 2, 2, 3, 1],
-            vec![3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 2],
-        ]
-    }
-}
-
 // pub fn handle_collisions(
 //     mut player_query: Query<(Entity, &mut Transform, &Collider), With<Player>>,
 //     collider_query: Query<(Entity, &Collider), With<Collision>>,
@@ -0,0 +1,246 @@
+use bincode::serialize;
+use renet::transport::NetcodeServerTransport;
+use renet::{ ClientId, DefaultChannel, RenetServer };
+use std::collections::HashMap;
+use std::io::{ stdin, BufRead };
+use std::sync::mpsc::{ channel, Receiver };
+use std::thread;
+use std::time::Duration;
+use store::{ EndGameReason, GameEvent, Maze, Stage };
+
+use crate::{ BanList, BanTarget, Command, Room, COMMANDS };
+
+/// Lines typed on the server's stdin, read from a thread of their own so that the game
+/// never waits for them
+pub fn spawn_console() -> Receiver<String> {
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        for line in stdin().lock().lines().map_while(Result::ok) {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Tells a client why it is disconnected, then disconnects it
+pub fn kick(
+    server: &mut RenetServer,
+    transport: &mut NetcodeServerTransport,
+    client_id: ClientId,
+    reason: &str
+) {
+    let event = GameEvent::Kicked { reason: reason.to_string() };
+    server.send_message(client_id, DefaultChannel::ReliableOrdered, serialize(&event).unwrap());
+    // the message must leave before the disconnection does
+    transport.send_packets(server);
+    server.disconnect(client_id);
+}
+
+/// What the admin commands act on, borrowed from the main loop
+pub struct Admin<'a> {
+    pub server: &'a mut RenetServer,
+    pub transport: &'a mut NetcodeServerTransport,
+    pub rooms: &'a mut HashMap<u32, Room>,
+    /// Names of the connected clients
+    pub names: &'a HashMap<ClientId, String>,
+    pub levels: &'a [Maze],
+    pub bans: &'a mut BanList,
+}
+
+impl Admin<'_> {
+    /// Parses and runs a line typed by an admin, returns the text to show to them
+    pub fn execute(&mut self, line: &str) -> String {
+        match line.parse::<Command>() {
+            Ok(command) => self.run(command),
+            Err(e) => format!("❌ {}", e),
+        }
+    }
+
+    /// Runs a command, returns the text to show to the admin
+    pub fn run(&mut self, command: Command) -> String {
+        match command {
+            Command::Help => COMMANDS.to_string(),
+            Command::Status => self.status(),
+            Command::Players => self.players(),
+            Command::Kick { client_id } => {
+                let client_id = ClientId::from_raw(client_id);
+                if !self.server.is_connected(client_id) {
+                    return format!("❌ no client {}", client_id);
+                }
+                kick(self.server, self.transport, client_id, "kicked by an admin");
+                format!("👢 kicked client {}", client_id)
+            }
+            Command::Ban { target, duration, reason } => self.ban(target, duration, reason),
+            Command::Unban { target } =>
+                match self.bans.remove(&target) {
+                    Ok(true) => format!("✅ unbanned {}", target),
+                    Ok(false) => format!("❌ {} isn't banned", target),
+                    Err(e) => format!("❌ {}", e),
+                }
+            Command::Bans => {
+                let bans: Vec<String> = self.bans.bans
+                    .iter()
+                    .filter(|ban| !ban.is_expired())
+                    .map(|ban| ban.to_string())
+                    .collect();
+                if bans.is_empty() {
+                    return "nobody is banned".to_string();
+                }
+                bans.join("\n")
+            }
+            Command::Map { level, room } => {
+                let found = self.levels
+                    .iter()
+                    .enumerate()
+                    .find(|(i, maze)| {
+                        level == (i + 1).to_string() || maze.name.eq_ignore_ascii_case(&level)
+                    });
+                let (lvl, maze) = match found {
+                    Some((i, maze)) => (i + 1, maze.clone()),
+                    None => {
+                        return format!("❌ no level {}", level);
+                    }
+                };
+                match self.rooms.get_mut(&room) {
+                    Some(room) => {
+                        room.restart(self.server, lvl, maze);
+                        format!("🗺 {} goes to level {}", room.name, lvl)
+                    }
+                    None => format!("❌ no room {}", room),
+                }
+            }
+            Command::Restart { room } =>
+                match self.rooms.get_mut(&room) {
+                    Some(room) => {
+                        let maze = room.game_state.maze.clone();
+                        room.restart(self.server, room.game_state.lvl, maze);
+                        format!("🔁 restarted {}", room.name)
+                    }
+                    None => format!("❌ no room {}", room),
+                }
+            Command::Say { text } => {
+                let event = GameEvent::ServerMessage { text: text.clone() };
+                let message = serialize(&event).unwrap();
+                self.server.broadcast_message(DefaultChannel::ReliableOrdered, message);
+                format!("📢 {}", text)
+            }
+            Command::End { room } =>
+                match self.rooms.get_mut(&room) {
+                    Some(room) if room.game_state.stage == Stage::InGame => {
+                        room.end(self.server, EndGameReason::Stopped, self.levels);
+                        format!("🟥 ended the game of {}", room.name)
+                    }
+                    Some(room) => format!("❌ {} isn't playing", room.name),
+                    None => format!("❌ no room {}", room),
+                }
+        }
+    }
+
+    fn sorted_rooms(&self) -> Vec<&Room> {
+        let mut rooms: Vec<&Room> = self.rooms.values().collect();
+        rooms.sort_by_key(|room| room.id);
+        rooms
+    }
+
+    fn status(&self) -> String {
+        let mut lines = vec![
+            format!(
+                "{} clients connected, {} rooms",
+                self.server.connected_clients(),
+                self.rooms.len()
+            )
+        ];
+        for room in self.sorted_rooms() {
+            let info = room.info();
+            lines.push(
+                format!(
+                    "[{}] {}: level {} ({}), {:?}, {:?}, {}/{} players",
+                    info.id,
+                    info.name,
+                    info.lvl,
+                    info.level,
+                    info.mode,
+                    info.stage,
+                    info.players,
+                    info.max_players
+                )
+            );
+        }
+        lines.join("\n")
+    }
+
+    fn address(&self, client_id: u64) -> String {
+        self.transport
+            .client_addr(ClientId::from_raw(client_id))
+            .map(|addr| addr.to_string())
+            .unwrap_or_default()
+    }
+
+    fn players(&self) -> String {
+        let mut lines = Vec::new();
+        for room in self.sorted_rooms() {
+            lines.push(format!("[{}] {}", room.id, room.name));
+            let mut players: Vec<_> = room.game_state.players.values().collect();
+            players.sort_by_key(|player| player.id);
+            for player in players {
+                lines.push(
+                    format!(
+                        "  client {} {} [{}] {} lives, {} kills, {} deaths {}",
+                        player.client_id,
+                        player.name,
+                        player.id,
+                        player.lives,
+                        player.score.kills,
+                        player.score.deaths,
+                        self.address(player.client_id)
+                    )
+                );
+            }
+            for (client_id, name) in &room.game_state.spectators {
+                lines.push(
+                    format!("  client {} {} spectating {}", client_id, name, self.address(*client_id))
+                );
+            }
+        }
+        for (client_id, name) in self.names {
+            if !self.rooms.values().any(|room| room.clients.contains(client_id)) {
+                lines.push(
+                    format!(
+                        "client {} {} picking a room {}",
+                        client_id,
+                        name,
+                        self.address(client_id.raw())
+                    )
+                );
+            }
+        }
+        if lines.is_empty() {
+            return "nobody is connected".to_string();
+        }
+        lines.join("\n")
+    }
+
+    fn ban(&mut self, target: BanTarget, duration: Option<Duration>, reason: String) -> String {
+        let ban = match self.bans.add(target, reason, duration) {
+            Ok(ban) => ban.clone(),
+            Err(e) => {
+                return format!("❌ {}", e);
+            }
+        };
+        let banned: Vec<ClientId> = self.server
+            .clients_id()
+            .into_iter()
+            .filter(|client_id| {
+                let name = self.names.get(client_id).map_or("", |name| name.as_str());
+                let ip = self.transport.client_addr(*client_id).map(|addr| addr.ip());
+                ban.target.matches(name, client_id.raw(), ip)
+            })
+            .collect();
+        for client_id in &banned {
+            kick(self.server, self.transport, *client_id, &ban.message());
+        }
+        format!("🚫 banned {}, {} clients kicked", ban, banned.len())
+    }
+}
@@ -0,0 +1,262 @@
+//! Bans kept in a JSON file, so that they outlive the server.
+//!
+//! The file holds a list of entries such as
+//! `{ "target": { "Ip": "10.0.0.7" }, "reason": "cheating", "expires_at": 1767225600 }`,
+//! `expires_at` being in seconds since the unix epoch, or null for a ban that never ends.
+
+use serde::{ Deserialize, Serialize };
+use std::net::IpAddr;
+use std::path::{ Path, PathBuf };
+use std::time::{ Duration, SystemTime };
+use std::{ fmt, fs };
+
+use crate::ConfigError;
+
+/// Who a ban keeps out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BanTarget {
+    Ip(IpAddr),
+    ClientId(u64),
+    Name(String),
+}
+
+impl BanTarget {
+    /// `id:<client id>`, an ip address, or else a player name
+    pub fn parse(target: &str) -> Result<Self, String> {
+        if let Some(id) = target.strip_prefix("id:") {
+            return id
+                .parse()
+                .map(BanTarget::ClientId)
+                .map_err(|_| format!("invalid client id: {}", id));
+        }
+        Ok(match target.parse() {
+            Ok(ip) => BanTarget::Ip(ip),
+            Err(_) => BanTarget::Name(target.to_string()),
+        })
+    }
+
+    pub fn matches(&self, name: &str, client_id: u64, ip: Option<IpAddr>) -> bool {
+        match self {
+            BanTarget::Ip(banned) => ip == Some(*banned),
+            BanTarget::ClientId(banned) => client_id == *banned,
+            BanTarget::Name(banned) => name == banned,
+        }
+    }
+}
+
+impl fmt::Display for BanTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BanTarget::Ip(ip) => write!(f, "{}", ip),
+            BanTarget::ClientId(client_id) => write!(f, "id:{}", client_id),
+            BanTarget::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ban {
+    pub target: BanTarget,
+    pub reason: String,
+    /// Seconds since the unix epoch, None bans for good
+    pub expires_at: Option<u64>,
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// `30s`, `10m`, `2h` or `7d`, the unit is required
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let (number, unit) = text.split_at(text.find(|c: char| !c.is_ascii_digit())?);
+    let number: u64 = number.parse().ok()?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => {
+            return None;
+        }
+    };
+    Some(Duration::from_secs(number.checked_mul(seconds)?))
+}
+
+impl Ban {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now())
+    }
+
+    /// The reason and the time left, if any
+    fn details(&self) -> String {
+        let mut details = String::new();
+        if !self.reason.is_empty() {
+            details += &format!(": {}", self.reason);
+        }
+        if let Some(expires_at) = self.expires_at {
+            let left = expires_at.saturating_sub(now());
+            details += &format!(" ({}h{:02}m left)", left / 3600, (left % 3600) / 60);
+        }
+        details
+    }
+
+    /// What the banned client is told
+    pub fn message(&self) -> String {
+        format!("banned from this server{}", self.details())
+    }
+}
+
+impl fmt::Display for Ban {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.target, self.details())
+    }
+}
+
+/// The bans of the server and the file they are saved to
+#[derive(Debug, Clone, PartialEq)]
+pub struct BanList {
+    pub path: PathBuf,
+    pub bans: Vec<Ban>,
+}
+
+impl BanList {
+    /// Reads the bans of `path`, a missing file is an empty list
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let mut list = BanList { path: path.to_path_buf(), bans: Vec::new() };
+        if !path.exists() {
+            return Ok(list);
+        }
+        let text = fs
+            ::read_to_string(path)
+            .map_err(|e| ConfigError(format!("cannot read {}: {}", path.display(), e)))?;
+        list.bans = serde_json
+            ::from_str(&text)
+            .map_err(|e| ConfigError(format!("invalid ban list {}: {}", path.display(), e)))?;
+        list.bans.retain(|ban| !ban.is_expired());
+        Ok(list)
+    }
+
+    pub fn save(&self) -> Result<(), ConfigError> {
+        let text = serde_json::to_string_pretty(&self.bans).unwrap();
+        fs::write(&self.path, text + "\n").map_err(|e| {
+            ConfigError(format!("cannot write {}: {}", self.path.display(), e))
+        })
+    }
+
+    /// The ban keeping this client out, if any
+    pub fn find(&self, name: &str, client_id: u64, ip: Option<IpAddr>) -> Option<&Ban> {
+        self.bans
+            .iter()
+            .find(|ban| !ban.is_expired() && ban.target.matches(name, client_id, ip))
+    }
+
+    /// Bans `target`, replacing its previous ban, and saves the list
+    pub fn add(
+        &mut self,
+        target: BanTarget,
+        reason: String,
+        duration: Option<Duration>
+    ) -> Result<&Ban, ConfigError> {
+        self.bans.retain(|ban| ban.target != target && !ban.is_expired());
+        let expires_at = duration.map(|duration| now() + duration.as_secs());
+        self.bans.push(Ban { target, reason, expires_at });
+        self.save()?;
+        Ok(self.bans.last().unwrap())
+    }
+
+    /// Lifts the ban of `target` and saves the list, false if it wasn't banned
+    pub fn remove(&mut self, target: &BanTarget) -> Result<bool, ConfigError> {
+        let count = self.bans.len();
+        self.bans.retain(|ban| ban.target != *target);
+        if self.bans.len() == count {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_need_a_known_unit() {
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("10m"), Some(Duration::from_secs(600)));
+        assert_eq!(parse_duration("2h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("7d"), Some(Duration::from_secs(7 * 86400)));
+        assert_eq!(parse_duration("5"), None);
+        assert_eq!(parse_duration("5w"), None);
+        assert_eq!(parse_duration("h"), None);
+        assert_eq!(parse_duration("-5m"), None);
+        assert!(parse_duration(&format!("{}d", u64::MAX / 86400)).is_some());
+        assert_eq!(parse_duration(&format!("{}d", u64::MAX / 86400 + 1)), None);
+        assert_eq!(parse_duration("99999999999999999999s"), None);
+    }
+
+    #[test]
+    fn targets_are_ids_ips_or_names() {
+        assert_eq!(BanTarget::parse("id:42"), Ok(BanTarget::ClientId(42)));
+        assert_eq!(BanTarget::parse("10.0.0.7"), Ok(BanTarget::Ip("10.0.0.7".parse().unwrap())));
+        assert_eq!(BanTarget::parse("::1"), Ok(BanTarget::Ip("::1".parse().unwrap())));
+        assert_eq!(BanTarget::parse("neo"), Ok(BanTarget::Name("neo".to_string())));
+        assert_eq!(BanTarget::parse("id:abc"), Err("invalid client id: abc".to_string()));
+        assert!(BanTarget::parse("id:").is_err());
+
+        for target in ["id:42", "10.0.0.7", "::1", "neo"] {
+            assert_eq!(BanTarget::parse(target).unwrap().to_string(), target);
+        }
+        let ip = Some("10.0.0.7".parse().unwrap());
+        assert!(BanTarget::parse("10.0.0.7").unwrap().matches("trinity", 1, ip));
+        assert!(!BanTarget::parse("10.0.0.8").unwrap().matches("trinity", 1, ip));
+        assert!(BanTarget::parse("id:1").unwrap().matches("trinity", 1, None));
+        assert!(!BanTarget::parse("neo").unwrap().matches("trinity", 1, ip));
+    }
+
+    #[test]
+    fn bans_expire() {
+        let ban = |expires_at| Ban {
+            target: BanTarget::ClientId(1),
+            reason: String::new(),
+            expires_at,
+        };
+        assert!(!ban(None).is_expired());
+        assert!(!ban(Some(now() + 60)).is_expired());
+        assert!(ban(Some(now())).is_expired());
+        assert!(ban(Some(0)).is_expired());
+    }
+
+    #[test]
+    fn bans_are_saved_and_loaded() {
+        let path = std::env::temp_dir().join(format!("server-bans-{}.json", std::process::id()));
+        let mut list = BanList::load(&path).unwrap();
+        assert!(list.bans.is_empty());
+
+        let neo = BanTarget::Name("neo".to_string());
+        list.add(neo.clone(), "spamming".to_string(), Some(Duration::from_secs(3600))).unwrap();
+        list.add(BanTarget::ClientId(7), String::new(), None).unwrap();
+        // banning again replaces the previous ban
+        list.add(neo.clone(), "cheating".to_string(), None).unwrap();
+        assert_eq!(list.bans.len(), 2);
+        assert_eq!(BanList::load(&path), Ok(list.clone()));
+        assert_eq!(list.find("neo", 3, None).map(|ban| ban.reason.as_str()), Some("cheating"));
+        assert!(list.find("trinity", 3, None).is_none());
+
+        assert_eq!(list.remove(&neo), Ok(true));
+        assert_eq!(list.remove(&neo), Ok(false));
+        let loaded = BanList::load(&path).unwrap();
+        assert_eq!(loaded.bans.len(), 1);
+        assert_eq!(loaded.bans[0].target, BanTarget::ClientId(7));
+        assert_eq!(loaded.bans[0].expires_at, None);
+
+        // expired bans are dropped when loading
+        list.bans.push(Ban { target: neo, reason: String::new(), expires_at: Some(1) });
+        list.save().unwrap();
+        assert_eq!(BanList::load(&path).unwrap().bans.len(), 1);
+
+        fs::write(&path, "not json").unwrap();
+        assert!(BanList::load(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,67 @@
+use std::io::{ stdin, stdout, BufRead, Write };
+use std::net::{ SocketAddr, ToSocketAddrs };
+use std::time::Duration;
+use server::{ rcon_request, COMMANDS };
+
+const USAGE: &str =
+    "Usage: rcon <HOST:PORT> [COMMAND]...
+
+Sends an admin command to the remote console of a server and prints the answer.
+Without a command, the commands are read from stdin, one per line.
+The password is read from the RCON_PASSWORD environment variable, or asked first.";
+
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}\n\n{}", USAGE, COMMANDS);
+        return;
+    }
+    let server_addr: SocketAddr = match
+        args[0]
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addrs| addrs.next())
+    {
+        Some(addr) => addr,
+        None => {
+            eprintln!("❌ invalid server address: {}", args[0]);
+            std::process::exit(1);
+        }
+    };
+
+    let mut lines = stdin().lock().lines().map_while(Result::ok);
+    let password = match std::env::var("RCON_PASSWORD") {
+        Ok(password) => password,
+        Err(_) => {
+            print!("Password: ");
+            stdout().flush().unwrap();
+            lines.next().unwrap_or_default()
+        }
+    };
+    let send = |command: &str| -> bool {
+        match rcon_request(server_addr, &password, command, TIMEOUT) {
+            Ok(answer) => {
+                println!("{}", answer);
+                true
+            }
+            Err(e) => {
+                eprintln!("❌ no answer from {}: {}", server_addr, e);
+                false
+            }
+        }
+    };
+
+    if args.len() > 1 {
+        if !send(&args[1..].join(" ")) {
+            std::process::exit(1);
+        }
+        return;
+    }
+    for line in lines {
+        if !line.trim().is_empty() {
+            send(line.trim());
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Commands typed on the admin console or sent to the remote one
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::{ parse_duration, BanTarget };
+
+pub const COMMANDS: &str =
+    "Commands:
+  status                  rooms and clients of the server
+  players                 every client, with its id, room and score
+  kick <client id>        disconnect a client
+  ban <target> [time] [reason]
+                          kick and keep out a name, an ip or id:<client id>, for
+                          a time such as 30m, 2h or 7d, or for good
+  unban <target>          lift a ban
+  bans                    every ban
+  map <level> [room]      restart a room on a level, by number or name
+  restart [room]          send everyone in a room back to its lobby
+  say <message>           show a message to every player
+  end [room]              end the game of a room
+  help                    this list
+Rooms are given by id, the main room (0) by default.";
+
+/// A command of the admin console
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Help,
+    Status,
+    Players,
+    Kick {
+        client_id: u64,
+    },
+    Ban {
+        target: BanTarget,
+        duration: Option<Duration>,
+        reason: String,
+    },
+    Unban {
+        target: BanTarget,
+    },
+    Bans,
+    Map {
+        level: String,
+        room: u32,
+    },
+    Restart {
+        room: u32,
+    },
+    Say {
+        text: String,
+    },
+    End {
+        room: u32,
+    },
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.trim();
+        let (name, args) = line.split_once(' ').unwrap_or((line, ""));
+        let args = args.trim();
+        let room = |arg: Option<&str>| -> Result<u32, String> {
+            match arg {
+                Some(room) => room.parse().map_err(|_| format!("invalid room: {}", room)),
+                None => Ok(0),
+            }
+        };
+        let mut words = args.split_whitespace();
+        match name {
+            "help" => Ok(Command::Help),
+            "status" => Ok(Command::Status),
+            "players" => Ok(Command::Players),
+            "kick" =>
+                args
+                    .parse()
+                    .map(|client_id| Command::Kick { client_id })
+                    .map_err(|_| "usage: kick <client id>".to_string()),
+            "ban" =>
+                match words.next() {
+                    Some(target) => {
+                        let mut words = words.peekable();
+                        let duration = match words.peek() {
+                            // rather than guessing whether a number is a time or a reason
+                            Some(word) if word.bytes().all(|b| b.is_ascii_digit()) => {
+                                return Err(format!("{} needs a unit, such as {}m", word, word));
+                            }
+                            Some(word) => parse_duration(word),
+                            None => None,
+                        };
+                        if duration.is_some() {
+                            words.next();
+                        }
+                        Ok(Command::Ban {
+                            target: BanTarget::parse(target)?,
+                            duration,
+                            reason: words.collect::<Vec<_>>().join(" "),
+                        })
+                    }
+                    None => Err("usage: ban <target> [time] [reason]".to_string()),
+                }
+            "unban" if args.is_empty() => Err("usage: unban <target>".to_string()),
+            "unban" => Ok(Command::Unban { target: BanTarget::parse(args)? }),
+            "bans" => Ok(Command::Bans),
+            "map" =>
+                match words.next() {
+                    Some(level) =>
+                        Ok(Command::Map {
+                            level: level.to_string(),
+                            room: room(words.next())?,
+                        }),
+                    None => Err("usage: map <level> [room]".to_string()),
+                }
+            "restart" => Ok(Command::Restart { room: room(words.next())? }),
+            "say" if args.is_empty() => Err("usage: say <message>".to_string()),
+            "say" => Ok(Command::Say { text: args.to_string() }),
+            "end" => Ok(Command::End { room: room(words.next())? }),
+            _ => Err(format!("unknown command: {}, try help", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Command, String> {
+        line.parse()
+    }
+
+    #[test]
+    fn simple_commands_parse() {
+        assert_eq!(parse("help"), Ok(Command::Help));
+        assert_eq!(parse("  status "), Ok(Command::Status));
+        assert_eq!(parse("players"), Ok(Command::Players));
+        assert_eq!(parse("bans"), Ok(Command::Bans));
+        assert_eq!(parse("kick 42"), Ok(Command::Kick { client_id: 42 }));
+        assert_eq!(parse("kick neo"), Err("usage: kick <client id>".to_string()));
+        let say = Command::Say { text: "hello  there".to_string() };
+        assert_eq!(parse("say  hello  there"), Ok(say));
+        assert!(parse("say").is_err());
+        assert_eq!(parse("shutdown"), Err("unknown command: shutdown, try help".to_string()));
+    }
+
+    #[test]
+    fn rooms_default_to_the_main_one() {
+        assert_eq!(parse("map 2"), Ok(Command::Map { level: "2".to_string(), room: 0 }));
+        let map = Command::Map { level: "crossroads".to_string(), room: 3 };
+        assert_eq!(parse("map crossroads 3"), Ok(map));
+        assert_eq!(parse("map 2 lobby"), Err("invalid room: lobby".to_string()));
+        assert_eq!(parse("map 2 -1"), Err("invalid room: -1".to_string()));
+        assert!(parse("map").is_err());
+        assert_eq!(parse("restart"), Ok(Command::Restart { room: 0 }));
+        assert_eq!(parse("restart 4"), Ok(Command::Restart { room: 4 }));
+        assert_eq!(parse("end 1"), Ok(Command::End { room: 1 }));
+        assert!(parse("end first").is_err());
+    }
+
+    #[test]
+    fn bans_take_an_optional_time_and_reason() {
+        let ban = Command::Ban {
+            target: BanTarget::Name("neo".to_string()),
+            duration: Some(Duration::from_secs(2 * 3600)),
+            reason: "spamming the chat".to_string(),
+        };
+        assert_eq!(parse("ban neo 2h spamming the chat"), Ok(ban));
+        let ban = Command::Ban {
+            target: BanTarget::ClientId(7),
+            duration: None,
+            reason: "cheating".to_string(),
+        };
+        assert_eq!(parse("ban id:7 cheating"), Ok(ban));
+        let ban = Command::Ban {
+            target: BanTarget::Ip("10.0.0.7".parse().unwrap()),
+            duration: Some(Duration::from_secs(30)),
+            reason: String::new(),
+        };
+        assert_eq!(parse("ban 10.0.0.7 30s"), Ok(ban));
+        assert_eq!(parse("ban neo 5 spamming"), Err("5 needs a unit, such as 5m".to_string()));
+        assert_eq!(parse("ban id:abc"), Err("invalid client id: abc".to_string()));
+        assert!(parse("ban").is_err());
+
+        let unban = Command::Unban { target: BanTarget::Name("neo".to_string()) };
+        assert_eq!(parse("unban neo"), Ok(unban));
+        assert!(parse("unban").is_err());
+        assert!(parse("unban id:").is_err());
+    }
+}
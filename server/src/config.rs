@@ -0,0 +1,406 @@
+use serde::{ Deserialize, Serialize };
+use std::net::IpAddr;
+use std::path::{ Path, PathBuf };
+use std::time::Duration;
+use std::{ fmt, fs };
+use store::{
+    maps_dir,
+    GameMode,
+    LobbyConfig,
+    LOBBY_COUNTDOWN,
+    MIN_PLAYERS,
+    NBR_OF_LIVES,
+    RCON_PORT_OFFSET,
+    TOKEN_PORT_OFFSET,
+};
+
+use crate::{ MAX_ROOMS, PLAYER_LIMIT };
+
+pub const USAGE: &str =
+    "Usage: server [OPTIONS]
+
+Options:
+  --config <FILE>      JSON file holding any of the settings below
+  --bind <IP>          address to listen on (default: the local ip)
+  --public <IP>        address the clients connect to, written in their connect
+                       tokens (default: the bind address, or the local ip for 0.0.0.0)
+  --port <PORT>        port to listen on (default: 8080)
+  --level <N>          level of the main room, see the level prompt for the numbers
+  --map <FILE>         maze file played in the main room instead of a level
+  --maps <DIR>         directory of the custom levels (default: $MAZE_MAPS_DIR, or
+                       maps next to the server, or maps in the working directory)
+  --mode <MODE>        lms (last man standing) or deathmatch
+  --rotation <N,N,..>  levels played after the first one
+  --generate <SEED>    play mazes generated from this seed instead of the built-in
+                       levels, as hard as the level they replace
+  --max-players <N>    players in a room
+  --max-rooms <N>      rooms hosted at the same time
+  --min-players <N>    players needed to start a game
+  --min-ready <N>      start once this many players are ready
+  --countdown <SECS>   length of the lobby countdown
+  --lives <N>          lives of a player when it spawns
+  --tick-rate <HZ>     server updates per second
+  --key <FILE>         private key of the secure mode, created when missing. Without
+                       it anyone can connect with any client id
+  --token-port <PORT>  TCP port the connect tokens are issued on (default: port + 1)
+  --bans <FILE>        JSON file the bans are kept in (default: bans.json)
+  --rcon-password <PW> password of the remote admin console, which is off without it
+  --rcon-port <PORT>   UDP port of the remote admin console (default: port + 2)
+  -h, --help           print this help
+
+Without --level or --map, the level, mode and rotation are asked on stdin.";
+
+/// Why the configuration could not be read
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError(pub String);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Settings of the server, from a JSON config file then the command line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// None listens on the local ip
+    pub bind: Option<IpAddr>,
+    /// None is the bind address, or the local ip when listening on every address
+    pub public_address: Option<IpAddr>,
+    pub port: u16,
+    pub level: Option<usize>,
+    pub map: Option<PathBuf>,
+    /// None looks for the custom levels where `store::maps_dir` says
+    pub maps_dir: Option<PathBuf>,
+    pub mode: Option<GameMode>,
+    pub rotation: Option<Vec<usize>>,
+    /// Replaces the built-in levels with mazes generated from this seed
+    pub generate_seed: Option<u64>,
+    pub max_players: usize,
+    pub max_rooms: usize,
+    pub min_players: usize,
+    pub min_ready: Option<usize>,
+    pub countdown: u8,
+    pub lives: u8,
+    pub tick_rate: u64,
+    /// Key signing the connect tokens, the server runs in secure mode when it is set
+    pub private_key_file: Option<PathBuf>,
+    /// None issues the tokens on the port after the game port
+    pub token_port: Option<u16>,
+    pub ban_file: PathBuf,
+    /// The remote admin console only runs with a password
+    pub rcon_password: Option<String>,
+    /// None listens two ports after the game port
+    pub rcon_port: Option<u16>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bind: None,
+            public_address: None,
+            port: 8080,
+            level: None,
+            map: None,
+            maps_dir: None,
+            mode: None,
+            rotation: None,
+            generate_seed: None,
+            max_players: PLAYER_LIMIT,
+            max_rooms: MAX_ROOMS,
+            min_players: MIN_PLAYERS,
+            min_ready: None,
+            countdown: LOBBY_COUNTDOWN,
+            lives: NBR_OF_LIVES,
+            tick_rate: 60,
+            private_key_file: None,
+            token_port: None,
+            ban_file: PathBuf::from("bans.json"),
+            rcon_password: None,
+            rcon_port: None,
+        }
+    }
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, ConfigError> {
+    value.parse().map_err(|_| ConfigError(format!("invalid value for {}: {}", flag, value)))
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let text = fs
+            ::read_to_string(path)
+            .map_err(|e| ConfigError(format!("cannot read {}: {}", path.display(), e)))?;
+        serde_json
+            ::from_str(&text)
+            .map_err(|e| ConfigError(format!("invalid config {}: {}", path.display(), e)))
+    }
+
+    /// Reads the config file given with `--config`, if any, then applies the other flags.
+    /// Every flag takes a value: `-h`/`--help` is looked for by `main` before calling this
+    pub fn from_args(args: &[String]) -> Result<Self, ConfigError> {
+        let mut pairs = Vec::new();
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| ConfigError(format!("missing value for {}", flag)))?;
+            pairs.push((flag.as_str(), value.as_str()));
+        }
+
+        let mut config = match pairs.iter().find(|(flag, _)| *flag == "--config") {
+            Some((_, path)) => Config::load(Path::new(path))?,
+            None => Config::default(),
+        };
+        for (flag, value) in pairs {
+            match flag {
+                "--config" => {}
+                "--bind" => {
+                    config.bind = Some(parse(flag, value)?);
+                }
+                "--public" => {
+                    config.public_address = Some(parse(flag, value)?);
+                }
+                "--port" => {
+                    config.port = parse(flag, value)?;
+                }
+                "--level" => {
+                    config.level = Some(parse(flag, value)?);
+                }
+                "--map" => {
+                    config.map = Some(PathBuf::from(value));
+                }
+                "--maps" => {
+                    config.maps_dir = Some(PathBuf::from(value));
+                }
+                "--mode" => {
+                    config.mode = Some(match value {
+                        "lms" => GameMode::LastManStanding,
+                        "deathmatch" => GameMode::deathmatch(),
+                        _ => {
+                            return Err(ConfigError(format!("unknown mode: {}", value)));
+                        }
+                    });
+                }
+                "--rotation" => {
+                    let levels = value
+                        .split(',')
+                        .map(|lvl| parse(flag, lvl.trim()))
+                        .collect::<Result<Vec<usize>, ConfigError>>()?;
+                    config.rotation = Some(levels);
+                }
+                "--generate" => {
+                    config.generate_seed = Some(parse(flag, value)?);
+                }
+                "--max-players" => {
+                    config.max_players = parse(flag, value)?;
+                }
+                "--max-rooms" => {
+                    config.max_rooms = parse(flag, value)?;
+                }
+                "--min-players" => {
+                    config.min_players = parse(flag, value)?;
+                }
+                "--min-ready" => {
+                    config.min_ready = Some(parse(flag, value)?);
+                }
+                "--countdown" => {
+                    config.countdown = parse(flag, value)?;
+                }
+                "--lives" => {
+                    config.lives = parse(flag, value)?;
+                }
+                "--tick-rate" => {
+                    config.tick_rate = parse(flag, value)?;
+                }
+                "--key" => {
+                    config.private_key_file = Some(PathBuf::from(value));
+                }
+                "--token-port" => {
+                    config.token_port = Some(parse(flag, value)?);
+                }
+                "--bans" => {
+                    config.ban_file = PathBuf::from(value);
+                }
+                "--rcon-password" => {
+                    config.rcon_password = Some(value.to_string());
+                }
+                "--rcon-port" => {
+                    config.rcon_port = Some(parse(flag, value)?);
+                }
+                _ => {
+                    return Err(ConfigError(format!("unknown option: {}", flag)));
+                }
+            }
+        }
+        config.check()?;
+        Ok(config)
+    }
+
+    /// Rejects the settings the server can't run with
+    pub fn check(&self) -> Result<(), ConfigError> {
+        if self.max_players == 0 || self.max_rooms == 0 {
+            return Err(ConfigError("a server needs at least one room and one player".to_string()));
+        }
+        if self.min_players == 0 || self.min_players > self.max_players {
+            let message = format!("min players must be between 1 and {}", self.max_players);
+            return Err(ConfigError(message));
+        }
+        if self.lives == 0 {
+            return Err(ConfigError("players need at least one life".to_string()));
+        }
+        if self.tick_rate == 0 || self.tick_rate > 1000 {
+            return Err(ConfigError("the tick rate must be between 1 and 1000".to_string()));
+        }
+        if self.public_address.is_some_and(|ip| ip.is_unspecified()) {
+            let message = "the public address must be one the clients can reach";
+            return Err(ConfigError(message.to_string()));
+        }
+        if self.rcon_password.as_ref().is_some_and(|pw| pw.is_empty() || pw.contains('\n')) {
+            return Err(ConfigError("the rcon password must be one non-empty line".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Rejects a rotation going to levels past the `count` ones the server has
+    pub fn check_rotation(&self, count: usize) -> Result<(), ConfigError> {
+        let levels = self.rotation.iter().flatten();
+        match levels.into_iter().find(|lvl| !(1..=count).contains(*lvl)) {
+            Some(lvl) => Err(ConfigError(format!("there is no level {} to rotate to", lvl))),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the level, mode and rotation must be asked on stdin
+    pub fn is_interactive(&self) -> bool {
+        self.level.is_none() && self.map.is_none()
+    }
+
+    pub fn lobby(&self) -> LobbyConfig {
+        LobbyConfig {
+            min_players: self.min_players,
+            min_ready: self.min_ready,
+            countdown: self.countdown,
+        }
+    }
+
+    /// Address the clients connect to, given the one the server listens on.
+    /// None when it listens on every address and the local ip must be used
+    pub fn public_ip(&self, bind: IpAddr) -> Option<IpAddr> {
+        self.public_address.or(Some(bind).filter(|ip| !ip.is_unspecified()))
+    }
+
+    /// Directory of the custom levels
+    pub fn maps_dir(&self) -> PathBuf {
+        self.maps_dir.clone().unwrap_or_else(maps_dir)
+    }
+
+    pub fn token_port(&self) -> u16 {
+        self.token_port.unwrap_or(self.port.wrapping_add(TOKEN_PORT_OFFSET))
+    }
+
+    pub fn rcon_port(&self) -> u16 {
+        self.rcon_port.unwrap_or(self.port.wrapping_add(RCON_PORT_OFFSET))
+    }
+
+    /// Time between two server updates
+    pub fn tick(&self) -> Duration {
+        Duration::from_millis(1000 / self.tick_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn error(line: &str) -> String {
+        Config::from_args(&args(line)).unwrap_err().0
+    }
+
+    #[test]
+    fn flags_override_the_config_file() {
+        let path = std::env::temp_dir().join(format!("server-config-{}.json", std::process::id()));
+        fs::write(&path, r#"{ "port": 9000, "lives": 5, "rotation": [1] }"#).unwrap();
+        let line = format!(
+            "--lives 7 --config {} --rotation 2,3 --mode deathmatch --maps levels --generate 42",
+            path.display()
+        );
+        let config = Config::from_args(&args(&line));
+        fs::remove_file(&path).unwrap();
+
+        let config = config.unwrap();
+        assert_eq!(config.port, 9000);
+        assert_eq!(config.lives, 7);
+        assert_eq!(config.rotation, Some(vec![2, 3]));
+        assert_eq!(config.mode, Some(GameMode::deathmatch()));
+        assert_eq!(config.max_players, PLAYER_LIMIT);
+        assert_eq!(config.maps_dir(), PathBuf::from("levels"));
+        assert_eq!(config.generate_seed, Some(42));
+        assert_eq!(config.token_port(), 9000 + TOKEN_PORT_OFFSET);
+        assert_eq!(Config::from_args(&[]), Ok(Config::default()));
+    }
+
+    #[test]
+    fn bad_flags_are_reported() {
+        assert_eq!(error("--colour red"), "unknown option: --colour");
+        assert_eq!(error("--port 9000 --lives"), "missing value for --lives");
+        assert_eq!(error("--rotation 2,x"), "invalid value for --rotation: x");
+        assert_eq!(error("--port 70000"), "invalid value for --port: 70000");
+        assert_eq!(error("--mode ctf"), "unknown mode: ctf");
+        assert!(error("--config /nonexistent/server.json").starts_with("cannot read"));
+
+        let config = Config::from_args(&args("--mode lms")).unwrap();
+        assert_eq!(config.mode, Some(GameMode::LastManStanding));
+    }
+
+    #[test]
+    fn limits_are_checked() {
+        assert_eq!(Config::default().check(), Ok(()));
+        let broken = [
+            Config { max_players: 0, ..Config::default() },
+            Config { max_rooms: 0, ..Config::default() },
+            Config { min_players: 0, ..Config::default() },
+            Config { min_players: 3, max_players: 2, ..Config::default() },
+            Config { lives: 0, ..Config::default() },
+            Config { tick_rate: 0, ..Config::default() },
+            Config { tick_rate: 1001, ..Config::default() },
+            Config { rcon_password: Some(String::new()), ..Config::default() },
+            Config { rcon_password: Some("a\nb".to_string()), ..Config::default() },
+            Config { public_address: Some("0.0.0.0".parse().unwrap()), ..Config::default() },
+        ];
+        for config in broken {
+            assert!(config.check().is_err(), "{:?}", config);
+        }
+        assert!(error("--min-players 20 --max-players 4").starts_with("min players"));
+    }
+
+    #[test]
+    fn tokens_carry_a_reachable_address() {
+        let any: IpAddr = "0.0.0.0".parse().unwrap();
+        let lan: IpAddr = "192.168.1.20".parse().unwrap();
+        let public: IpAddr = "203.0.113.5".parse().unwrap();
+        assert_eq!(Config::default().public_ip(lan), Some(lan));
+        assert_eq!(Config::default().public_ip(any), None);
+        let config = Config::from_args(&args("--bind 0.0.0.0 --public 203.0.113.5")).unwrap();
+        assert_eq!(config.public_ip(any), Some(public));
+        assert_eq!(config.public_ip(lan), Some(public));
+    }
+
+    #[test]
+    fn rotations_stay_within_the_levels() {
+        assert_eq!(Config::default().check_rotation(3), Ok(()));
+        let config = Config::from_args(&args("--rotation 1,3")).unwrap();
+        assert_eq!(config.check_rotation(3), Ok(()));
+        assert!(config.check_rotation(2).is_err());
+        let config = Config::from_args(&args("--rotation 0")).unwrap();
+        let expected = ConfigError("there is no level 0 to rotate to".to_string());
+        assert_eq!(config.check_rotation(3), Err(expected));
+    }
+}
@@ -1,3 +1,17 @@
+mod admin;
+mod bans;
+mod command;
+mod config;
+mod rcon;
+mod room;
+mod token;
 mod tools;
 
+pub use admin::*;
+pub use bans::*;
+pub use command::*;
+pub use config::*;
+pub use rcon::*;
+pub use room::*;
+pub use token::*;
 pub use tools::*;
\ No newline at end of file
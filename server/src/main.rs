@@ -1,136 +1,211 @@
 use renet::transport::{ ServerAuthentication, ServerConfig, NetcodeServerTransport };
 use renet::{ ClientId, ConnectionConfig, DefaultChannel, RenetServer, ServerEvent };
-use store::GameState;
 use std::collections::HashMap;
 use std::net::{ SocketAddr, UdpSocket };
-use std::time::{ Duration, Instant, SystemTime };
-use std::{ u64, u8 };
+use std::time::SystemTime;
 use std::thread::*;
-use store::{ PROTOCOL_ID, GAME_FPS, * };
+use store::{ PROTOCOL_ID, * };
 use bincode::*;
 use server::*;
 use local_ip_address::local_ip;
 
 fn main() {
-    let ip_address = match local_ip() {
-        Ok(ip) => ip.to_string(), // Convertit l'adresse IP en chaîne de caractères
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let config = match Config::from_args(&args) {
+        Ok(config) => config,
         Err(e) => {
-            eprintln!("❌ Error while retrieving local Ip address: {}", e);
+            eprintln!("❌ {}\n\n{}", e, USAGE);
             return;
         }
     };
 
-    let port = 8080;
-    let ip_with_port = format!("{}:{}", ip_address, port);
-    let server_addr: SocketAddr = ip_with_port.parse().unwrap();
-    let socket: UdpSocket = UdpSocket::bind(server_addr).unwrap();
+    let ip_address = match config.bind {
+        Some(ip) => ip,
+        None =>
+            match local_ip() {
+                Ok(ip) => ip,
+                Err(e) => {
+                    eprintln!("❌ Error while retrieving local Ip address: {}", e);
+                    return;
+                }
+            }
+    };
+    let server_addr = SocketAddr::new(ip_address, config.port);
+    let socket: UdpSocket = match UdpSocket::bind(server_addr) {
+        Ok(socket) => socket,
+        Err(e) => {
+            eprintln!("❌ Cannot listen on {}: {}", server_addr, e);
+            return;
+        }
+    };
+    // netcode only accepts the connect tokens made for this address
+    let public_ip = match config.public_ip(ip_address) {
+        Some(ip) => ip,
+        None =>
+            match local_ip() {
+                Ok(ip) => ip,
+                Err(e) => {
+                    eprintln!("❌ Cannot find the address of the server, set --public: {}", e);
+                    return;
+                }
+            }
+    };
+    let public_addr = SocketAddr::new(public_ip, config.port);
+
+    // secure mode: only the clients holding a token signed with our key get in
+    let authentication = match &config.private_key_file {
+        Some(path) => {
+            let private_key = match load_or_create_key(path) {
+                Ok(private_key) => private_key,
+                Err(e) => {
+                    eprintln!("❌ {}", e);
+                    return;
+                }
+            };
+            let token_addr = SocketAddr::new(ip_address, config.token_port());
+            if let Err(e) = spawn_token_issuer(token_addr, public_addr, private_key) {
+                eprintln!("❌ Cannot issue tokens on {}: {}", token_addr, e);
+                return;
+            }
+            ServerAuthentication::Secure { private_key }
+        }
+        None => ServerAuthentication::Unsecure,
+    };
 
     let mut server = RenetServer::new(ConnectionConfig::default());
     let server_config = ServerConfig {
         current_time: SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap(),
-        max_clients: PLAYER_LIMIT,
+        max_clients: config.max_players * config.max_rooms,
         protocol_id: PROTOCOL_ID,
-        public_addresses: vec![server_addr],
-        authentication: ServerAuthentication::Unsecure,
+        public_addresses: vec![public_addr],
+        authentication,
     };
     let mut transport = NetcodeServerTransport::new(server_config, socket).unwrap();
-    let mut game_state = GameState::default();
-
-    let lvl = get_level();
-    game_state.set_lvl(lvl);
-    println!("🕹 maze server listening on {} 📡", server_addr);
 
-    let mut timer = Instant::now();
-    let mut count_sec = 20;
+    let mut levels = all_levels(&config.maps_dir(), config.generate_seed);
+    let (lvl, maze) = match configured_level(&config, &mut levels) {
+        Ok(level) => level,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
+    let mode = match config.mode {
+        Some(mode) => mode,
+        None if config.is_interactive() => get_mode(),
+        None => GameMode::default(),
+    };
+    let rotation = match configured_rotation(&config, lvl, levels.len()) {
+        Ok(rotation) => rotation,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
+    let tick = config.tick();
+    let mut bans = match BanList::load(&config.ban_file) {
+        Ok(bans) => bans,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
+    if !bans.bans.is_empty() {
+        println!("🚫 {} bans loaded from {}", bans.bans.len(), config.ban_file.display());
+    }
+    // the first room is always there, the others live as long as someone is in them
+    let mut rooms: HashMap<u32, Room> = HashMap::new();
+    rooms.insert(0, Room::new(0, "Main".to_string(), lvl, maze, mode, rotation, &config));
+    let mut next_room_id = 1;
+    // names of the connected clients, and the room each one is in
+    let mut names: HashMap<ClientId, String> = HashMap::new();
+    let mut client_rooms: HashMap<ClientId, u32> = HashMap::new();
+    // the prompts are done with stdin, from now on it takes admin commands
+    let console = spawn_console();
+    let mut rcon = match &config.rcon_password {
+        Some(password) => {
+            let rcon_addr = SocketAddr::new(ip_address, config.rcon_port());
+            match RconServer::bind(rcon_addr, password.clone()) {
+                Ok(rcon) => Some(rcon),
+                Err(e) => {
+                    eprintln!("❌ Cannot listen for remote admins on {}: {}", rcon_addr, e);
+                    return;
+                }
+            }
+        }
+        None => None,
+    };
+    println!("🕹 maze server listening on {}, reached on {} 📡", server_addr, public_addr);
+    println!("⌨ type help for the admin commands");
 
     loop {
-        // Receive new messages and update clients at desired fps
-        server.update(GAME_FPS);
-        transport.update(GAME_FPS, &mut server).expect("error while transporting from server");
+        // Receive new messages and update clients at the configured tick rate
+        server.update(tick);
+        transport.update(tick, &mut server).expect("error while transporting from server");
 
         while let Some(event) = server.get_event() {
             match event {
                 ServerEvent::ClientConnected { client_id } => {
                     // * ------ connection logic
-                    if game_state.stage != Stage::PreGame {
-                        server.send_message(
-                            client_id,
-                            DefaultChannel::ReliableOrdered,
-                            serialize(&GameEvent::AccessForbidden).unwrap()
-                        );
+                    let name = match
+                        transport
+                            .user_data(client_id)
+                            .ok_or(NameError::Empty)
+                            .and_then(|user_data| name_from_user_data(&user_data))
+                            .and_then(|name| validate_name(&name))
+                    {
+                        Ok(name) => name,
+                        Err(e) => {
+                            println!("❌ refused client {}: {}", client_id, e);
+                            let event = GameEvent::NameRejected { reason: e.to_string() };
+                            server.send_message(
+                                client_id,
+                                DefaultChannel::ReliableOrdered,
+                                serialize(&event).unwrap()
+                            );
+                            // the message must leave before the disconnection does
+                            transport.send_packets(&mut server);
+                            server.disconnect(client_id);
+                            continue;
+                        }
+                    };
+                    // before the client gets a room, and so a player id
+                    let ip = transport.client_addr(client_id).map(|addr| addr.ip());
+                    if let Some(ban) = bans.find(&name, client_id.raw(), ip) {
+                        println!("🚫 refused banned client {} {} ({})", client_id, name, ban.target);
+                        kick(&mut server, &mut transport, client_id, &ban.message());
                         continue;
                     }
-
-                    let player_id = game_state.generate_id();
-                    let spawn_coord = game_state.random_spawn();
-                    let event = GameEvent::PlayerJoined {
-                        player_id,
-                        name: "player".to_string(),
-                        position: spawn_coord.clone(),
-                        client_id: client_id.raw(),
-                    };
-                    println!("🟢 [{}] joined the server.", player_id);
-                    server.broadcast_message_except(
-                        client_id,
-                        DefaultChannel::ReliableOrdered,
-                        serialize(&event).expect("error while serializing event")
-                    );
-
-                    let id_event = GameEvent::Spawn {
-                        player_id,
-                        position: spawn_coord.clone(),
-                        lvl: game_state.lvl,
-                    };
-
+                    println!("🟢 {} connected, picking a room", name);
+                    names.insert(client_id, name);
                     server.send_message(
                         client_id,
                         DefaultChannel::ReliableOrdered,
-                        serialize(&id_event).expect("error while sending id to client")
+                        serialize(&room_list(&rooms)).unwrap()
                     );
-
-                    game_state.consume(&event, client_id.raw());
-
-                    if game_state.players.len() == PLAYER_LIMIT {
-                        let event = GameEvent::BeginGame {
-                            player_list: game_state.players.clone(),
-                        };
-                        game_state.consume(&event, client_id.raw());
-                        server.broadcast_message(0, serialize(&event).unwrap());
-                        println!("🟩 The game has begun");
-                    }
-                    break;
                 }
 
                 ServerEvent::ClientDisconnected { client_id, reason } => {
                     // * -------- disconnection logic
-                    let player_id = game_state.get_player_id(client_id.raw());
-                    // First consume a disconnect event
-                    let event = GameEvent::PlayerDisconnected { player_id };
-                    game_state.consume(&event, client_id.raw());
-                    server.broadcast_message(0, serialize(&event).unwrap());
-                    println!("🔻 Player [{}] disconnected due to \"{}\"", player_id, reason);
-
-                    if game_state.players.len() == 1 && game_state.stage == Stage::InGame {
-                        let event = GameEvent::EndGame;
-                        game_state.consume(&event, client_id.raw());
-                        server.broadcast_message(
-                            DefaultChannel::ReliableOrdered,
-                            serialize(&event).unwrap()
-                        );
-                        for (id, _) in &game_state.players {
-                            println!("✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨");
-                            println!("✨                                                  ✨");
-                            println!("✨               Player [{}] has won !              ✨", id);
-                            println!("✨                                                  ✨");
-                            println!("✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨");
+                    names.remove(&client_id);
+                    if let Some(room_id) = client_rooms.remove(&client_id) {
+                        if let Some(room) = rooms.get_mut(&room_id) {
+                            room.leave(&mut server, client_id, &reason.to_string());
+                            if room.is_empty() && room_id != 0 {
+                                println!("🗑 closing empty room {}", room.name);
+                                rooms.remove(&room_id);
+                            }
                         }
-                        println!("🟥 Game has ended");
                     }
                 }
             }
         }
 
-        // ! Receive GameEvents from clients. Broadcast valid events.
+        // ! Receive GameEvents from clients, room events are handled by their room
         for client_id in server.clients_id().into_iter() {
             while
                 let Some(message) = server.receive_message(
@@ -138,107 +213,119 @@ fn main() {
                     DefaultChannel::ReliableOrdered
                 )
             {
-                if let Ok(event) = deserialize::<GameEvent>(&message) {
-                    if game_state.validate(&event, client_id.raw()) {
-                        let broad_event = game_state.consume(&event, client_id.raw());
-                        //println!("[EVENT]: Client {} sent:\n\t{:#?}", client_id, broad_event);
-                        match broad_event {
-                            GameEvent::PlayerMove { .. } => {
-                                for client_id_p in server.clients_id().into_iter() {
-                                    let mut player_list: HashMap<u8, Players> = HashMap::new();
-                                    let id = game_state.get_player_id(client_id_p.raw());
-                                    for (idp, value) in game_state.players.clone() {
-                                        if !idp.eq(&id) {
-                                            player_list.insert(idp, value);
-                                        }
-                                    }
-                                    let begin_event = GameEvent::PlayerMove {
-                                        player_id: u8::MAX,
-                                        at: Position::default(),
-                                        player_list,
-                                        vision: (0.0, 0.0),
-                                    };
-                                    server.send_message(
-                                        client_id_p,
-                                        DefaultChannel::ReliableOrdered,
-                                        serialize(&begin_event).unwrap()
-                                    );
-                                }
-                            }
-                            GameEvent::Impact { id } => {
-                                let adress = game_state.get_client_id(id);
-                                server.send_message(
-                                    ClientId::from_raw(adress),
-                                    DefaultChannel::ReliableOrdered,
-                                    serialize(&broad_event).unwrap()
-                                );
-                            }
-                            _ => {
-                                server.broadcast_message(
-                                    DefaultChannel::ReliableOrdered,
-                                    serialize(&broad_event).unwrap()
+                let event = match deserialize::<GameEvent>(&message) {
+                    Ok(event) => event,
+                    Err(_) => {
+                        continue;
+                    }
+                };
+                // rooms are only joined once, by clients with a valid name
+                let joinable = !client_rooms.contains_key(&client_id);
+                let name = names.get(&client_id).cloned();
+                let joined = match (event, name) {
+                    (GameEvent::ListRooms, _) => {
+                        server.send_message(
+                            client_id,
+                            DefaultChannel::ReliableOrdered,
+                            serialize(&room_list(&rooms)).unwrap()
+                        );
+                        None
+                    }
+                    (GameEvent::JoinRoom { room_id }, Some(name)) if joinable => {
+                        let result = match rooms.get_mut(&room_id) {
+                            Some(room) => room.join(&mut server, client_id, &name),
+                            None => Err(format!("there is no room {}", room_id)),
+                        };
+                        Some(result.map(|_| room_id))
+                    }
+                    (GameEvent::CreateRoom { name: room_name, lvl: room_lvl, mode: room_mode }, Some(name)) if
+                        joinable
+                    => {
+                        let room_lvl = room_lvl.unwrap_or(lvl);
+                        let result = if rooms.len() >= config.max_rooms {
+                            Err("the server can't host more rooms".to_string())
+                        } else if room_lvl < 1 || room_lvl > levels.len() {
+                            Err(format!("there is no level {}", room_lvl))
+                        } else {
+                            validate_name(&room_name).map_err(|e| format!("invalid room name: {}", e))
+                        };
+                        Some(
+                            result.and_then(|room_name| {
+                                let room_id = next_room_id;
+                                let maze = levels[room_lvl - 1].clone();
+                                let mut room = Room::new(
+                                    room_id,
+                                    room_name,
+                                    room_lvl,
+                                    maze,
+                                    room_mode.unwrap_or(mode),
+                                    Rotation::after(room_lvl, levels.len()),
+                                    &config
                                 );
-                            }
-                        }
-
-                        // ^Determine if a player has won the game at each request
-                        if let Some(winner) = game_state.determine_winner() {
-                            let event = GameEvent::EndGame;
-                            game_state.stage = Stage::Ended;
-                            server.broadcast_message(
-                                DefaultChannel::ReliableOrdered,
-                                serialize(&event).unwrap()
-                            );
-                            println!("🟩 [INFO]: player with id [{}] won !", winner);
+                                println!("🏠 {} created room {}", name, room.name);
+                                room.join(&mut server, client_id, &name)?;
+                                next_room_id += 1;
+                                rooms.insert(room_id, room);
+                                Ok(room_id)
+                            })
+                        )
+                    }
+                    (GameEvent::JoinRoom { .. } | GameEvent::CreateRoom { .. }, _) => {
+                        Some(Err("you can't join a room".to_string()))
+                    }
+                    (event, _) => {
+                        let room = client_rooms
+                            .get(&client_id)
+                            .and_then(|room_id| rooms.get_mut(room_id));
+                        if let Some(room) = room {
+                            room.handle_message(&mut server, client_id, event);
                         }
+                        None
+                    }
+                };
+                match joined {
+                    Some(Ok(room_id)) => {
+                        client_rooms.insert(client_id, room_id);
                     }
+                    Some(Err(reason)) => {
+                        server.send_message(
+                            client_id,
+                            DefaultChannel::ReliableOrdered,
+                            serialize(&GameEvent::RoomRefused { reason }).unwrap()
+                        );
+                    }
+                    None => {}
                 }
             }
         }
 
-        if game_state.stage == Stage::PreGame {
-            if
-                timer.elapsed() > Duration::from_secs(1) &&
-                game_state.players.len() >= 2 &&
-                count_sec > 0
-            {
-                timer = Instant::now();
-                let timer_event = GameEvent::Timer { duration: count_sec };
-                for (_id, player) in &game_state.players {
-                    server.send_message(
-                        ClientId::from_raw(player.client_id),
-                        DefaultChannel::ReliableOrdered,
-                        serialize(&timer_event).unwrap()
-                    );
-                }
-
-                count_sec -= 1;
-                println!("tickling");
+        for room in rooms.values_mut() {
+            for client_id in room.update(&mut server, &levels) {
+                // back to picking a room
+                client_rooms.remove(&client_id);
             }
+        }
 
-            if count_sec == 0 {
-                println!("game has started");
-                let event = GameEvent::BeginGame { player_list: game_state.players.clone() };
-                game_state.consume(&event, u64::MAX); //sets game stage to InGame
-
-                for client_id in server.clients_id().into_iter() {
-                    let mut player_list: HashMap<u8, Players> = HashMap::new();
-                    let id = game_state.get_player_id(client_id.raw());
-                    for (idp, value) in game_state.players.clone() {
-                        if !idp.eq(&id) {
-                            player_list.insert(idp, value);
-                        }
-                    }
-                    let begin_event = GameEvent::BeginGame { player_list };
-                    server.send_message(
-                        client_id,
-                        DefaultChannel::ReliableOrdered,
-                        serialize(&begin_event).unwrap()
-                    );
-                }
+        // ! Admin commands, typed on the console or sent over rcon
+        let mut admin = Admin {
+            server: &mut server,
+            transport: &mut transport,
+            rooms: &mut rooms,
+            names: &names,
+            levels: &levels,
+            bans: &mut bans,
+        };
+        while let Ok(line) = console.try_recv() {
+            if !line.trim().is_empty() {
+                println!("{}", admin.execute(&line));
+            }
+        }
+        if let Some(rcon) = &mut rcon {
+            for (line, addr) in rcon.receive() {
+                rcon.reply(addr, &admin.execute(&line));
             }
         }
         transport.send_packets(&mut server);
-        sleep(GAME_FPS);
+        sleep(tick);
     }
 }
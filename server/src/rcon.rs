@@ -0,0 +1,192 @@
+//! Remote admin console over UDP.
+//!
+//! A request is a single datagram holding the password, a newline, then an admin command
+//! as typed on the console. The answer is a single datagram with the text the console
+//! would have printed.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::{ IpAddr, SocketAddr, UdpSocket };
+use std::time::{ Duration, Instant };
+
+/// Largest payload of a UDP datagram, longer answers are cut
+pub const RCON_MAX_PACKET: usize = 65507;
+/// Datagrams read during one tick, the others wait for the next ticks
+pub const RCON_MAX_PACKETS_PER_TICK: usize = 8;
+/// How long an address that sent a wrong password is ignored
+pub const RCON_LOCKOUT: Duration = Duration::from_secs(2);
+
+pub fn encode_request(password: &str, command: &str) -> Vec<u8> {
+    format!("{}\n{}", password, command).into_bytes()
+}
+
+/// The password and the command of a request
+pub fn decode_request(packet: &[u8]) -> Option<(&str, &str)> {
+    std::str::from_utf8(packet).ok()?.split_once('\n')
+}
+
+/// Compares two passwords in a time that doesn't depend on where they differ
+fn same_password(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut diff = a.len() ^ b.len();
+    for i in 0..a.len().max(b.len()) {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        diff |= (x ^ y) as usize;
+    }
+    diff == 0
+}
+
+fn truncate(text: &str) -> &str {
+    if text.len() <= RCON_MAX_PACKET {
+        return text;
+    }
+    let mut end = RCON_MAX_PACKET;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// The server side, polled from the game loop so that the commands run between two ticks
+pub struct RconServer {
+    socket: UdpSocket,
+    password: String,
+    /// Addresses that sent a wrong password, and until when they are ignored
+    lockouts: HashMap<IpAddr, Instant>,
+}
+
+impl RconServer {
+    pub fn bind(addr: SocketAddr, password: String) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        println!("🛰 remote admin listening on {}", addr);
+        Ok(Self { socket, password, lockouts: HashMap::new() })
+    }
+
+    /// The commands sent with the right password since the last call, at most
+    /// `RCON_MAX_PACKETS_PER_TICK`, with where to answer them. An address sending a
+    /// wrong password is told once, then ignored for `RCON_LOCKOUT`
+    pub fn receive(&mut self) -> Vec<(String, SocketAddr)> {
+        let now = Instant::now();
+        self.lockouts.retain(|_, until| *until > now);
+        let mut commands = Vec::new();
+        let mut buffer = vec![0u8; RCON_MAX_PACKET];
+        for _ in 0..RCON_MAX_PACKETS_PER_TICK {
+            let (len, addr) = match self.socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(_) => {
+                    break;
+                }
+            };
+            if self.lockouts.contains_key(&addr.ip()) {
+                continue;
+            }
+            match decode_request(&buffer[..len]) {
+                Some((password, command)) if same_password(password, &self.password) => {
+                    println!("🛰 {} sent: {}", addr, command);
+                    commands.push((command.to_string(), addr));
+                }
+                _ => {
+                    println!("❌ refused a remote command from {}", addr);
+                    self.lockouts.insert(addr.ip(), now + RCON_LOCKOUT);
+                    self.reply(addr, "❌ wrong password");
+                }
+            }
+        }
+        commands
+    }
+
+    pub fn reply(&self, addr: SocketAddr, text: &str) {
+        if let Err(e) = self.socket.send_to(truncate(text).as_bytes(), addr) {
+            println!("❌ cannot answer {}: {}", addr, e);
+        }
+    }
+}
+
+/// Sends a command to the RCON port of a server, returns its answer
+pub fn rcon_request(
+    server_addr: SocketAddr,
+    password: &str,
+    command: &str,
+    timeout: Duration
+) -> io::Result<String> {
+    let local_addr: SocketAddr = if server_addr.is_ipv4() {
+        "0.0.0.0:0".parse().unwrap()
+    } else {
+        "[::]:0".parse().unwrap()
+    };
+    let socket = UdpSocket::bind(local_addr)?;
+    socket.set_read_timeout(Some(timeout))?;
+    socket.connect(server_addr)?;
+    socket.send(&encode_request(password, command))?;
+    let mut buffer = vec![0u8; RCON_MAX_PACKET];
+    let len = socket.recv(&mut buffer)?;
+    Ok(String::from_utf8_lossy(&buffer[..len]).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+
+    #[test]
+    fn requests_round_trip() {
+        let packet = encode_request("hunter2", "map 2 1");
+        assert_eq!(decode_request(&packet), Some(("hunter2", "map 2 1")));
+        assert_eq!(decode_request(b"hunter2 status"), None);
+        assert_eq!(decode_request(&[0xff, b'\n', b'a']), None);
+    }
+
+    #[test]
+    fn passwords_must_match_exactly() {
+        assert!(same_password("hunter2", "hunter2"));
+        assert!(!same_password("hunter2", "hunter3"));
+        assert!(!same_password("hunter2", "hunter"));
+        assert!(!same_password("", "hunter2"));
+    }
+
+    #[test]
+    fn answers_are_cut_on_a_char_boundary() {
+        assert_eq!(truncate("status"), "status");
+        // 'é' takes two bytes, and the limit is odd
+        let text = "é".repeat(RCON_MAX_PACKET);
+        let cut = truncate(&text);
+        assert_eq!(cut.len(), RCON_MAX_PACKET - 1);
+        assert!(cut.chars().all(|c| c == 'é'));
+    }
+
+    fn server() -> (RconServer, SocketAddr, UdpSocket) {
+        let rcon = RconServer::bind("127.0.0.1:0".parse().unwrap(), "pw".to_string()).unwrap();
+        let addr = rcon.socket.local_addr().unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.set_read_timeout(Some(Duration::from_millis(200))).unwrap();
+        (rcon, addr, client)
+    }
+
+    #[test]
+    fn commands_are_read_a_few_per_tick() {
+        let (mut rcon, addr, client) = server();
+        for _ in 0..RCON_MAX_PACKETS_PER_TICK + 2 {
+            client.send_to(&encode_request("pw", "status"), addr).unwrap();
+        }
+        sleep(Duration::from_millis(50));
+        assert_eq!(rcon.receive().len(), RCON_MAX_PACKETS_PER_TICK);
+        assert_eq!(rcon.receive().len(), 2);
+    }
+
+    #[test]
+    fn wrong_passwords_lock_the_address_out() {
+        let (mut rcon, addr, client) = server();
+        for _ in 0..3 {
+            client.send_to(&encode_request("guess", "status"), addr).unwrap();
+        }
+        client.send_to(&encode_request("pw", "status"), addr).unwrap();
+        sleep(Duration::from_millis(50));
+        assert!(rcon.receive().is_empty());
+
+        let mut buffer = [0u8; 64];
+        assert!(client.recv(&mut buffer).is_ok());
+        assert!(client.recv(&mut buffer).is_err(), "answered every wrong password");
+    }
+}
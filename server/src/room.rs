@@ -0,0 +1,422 @@
+use bincode::serialize;
+use renet::{ ClientId, DefaultChannel, RenetServer };
+use std::collections::{ HashMap, HashSet };
+use std::time::{ Duration, Instant };
+use store::*;
+
+use crate::Config;
+
+/// An independent match: its own level, players and game state
+pub struct Room {
+    pub id: u32,
+    pub name: String,
+    pub game_state: GameState,
+    /// Every client in the room, including the spectators
+    pub clients: HashSet<ClientId>,
+    /// Levels of the next rounds
+    pub rotation: Rotation,
+    pub max_players: usize,
+    timer: Instant,
+}
+
+impl Room {
+    pub fn new(
+        id: u32,
+        name: String,
+        lvl: usize,
+        maze: Maze,
+        mode: GameMode,
+        rotation: Rotation,
+        config: &Config
+    ) -> Self {
+        let report = analyze(&maze);
+        if report.components > 1 {
+            println!("⚠️ {} has {} unreachable areas", maze.name, report.components - 1);
+        }
+        let mut game_state = GameState::default();
+        game_state.set_maze(lvl, maze);
+        game_state.mode = mode;
+        game_state.lobby = config.lobby();
+        game_state.max_lives = config.lives;
+        Self {
+            id,
+            name,
+            game_state,
+            clients: HashSet::new(),
+            rotation,
+            max_players: config.max_players,
+            timer: Instant::now(),
+        }
+    }
+
+    pub fn info(&self) -> RoomInfo {
+        RoomInfo {
+            id: self.id,
+            name: self.name.clone(),
+            lvl: self.game_state.lvl,
+            level: self.game_state.maze.name.clone(),
+            mode: self.game_state.mode,
+            stage: self.game_state.stage,
+            players: self.clients.len(),
+            max_players: self.max_players,
+            lives: self.game_state.max_lives,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    fn send(&self, server: &mut RenetServer, client_id: ClientId, event: &GameEvent) {
+        server.send_message(client_id, DefaultChannel::ReliableOrdered, serialize(event).unwrap());
+    }
+
+    fn broadcast(&self, server: &mut RenetServer, event: &GameEvent) {
+        let message = serialize(event).unwrap();
+        for client_id in &self.clients {
+            server.send_message(*client_id, DefaultChannel::ReliableOrdered, message.clone());
+        }
+    }
+
+    /// Sends each client of the room its own view of the players, without itself
+    fn send_player_lists(&self, server: &mut RenetServer, begin: bool) {
+        for client_id in &self.clients {
+            let mut player_list: HashMap<u8, Players> = HashMap::new();
+            let id = self.game_state.get_player_id(client_id.raw());
+            for (idp, value) in self.game_state.players.clone() {
+                if !idp.eq(&id) {
+                    player_list.insert(idp, value);
+                }
+            }
+            let event = if begin {
+                GameEvent::BeginGame { player_list }
+            } else {
+                GameEvent::PlayerMove {
+                    player_id: u8::MAX,
+                    at: Position::default(),
+                    player_list,
+                    vision: (0.0, 0.0),
+                }
+            };
+            self.send(server, *client_id, &event);
+        }
+    }
+
+    /// Adds a client to the lobby of the room, returns why it can't join otherwise
+    pub fn join(
+        &mut self,
+        server: &mut RenetServer,
+        client_id: ClientId,
+        requested_name: &str
+    ) -> Result<(), String> {
+        // a spectator joining the next round already is one of the clients
+        let others = self.clients.iter().filter(|id| **id != client_id).count();
+        if others >= self.max_players {
+            return Err(format!("{} is full", self.name));
+        }
+        let name = self.game_state.player_name(requested_name).map_err(|e| e.to_string())?;
+        if self.game_state.stage != Stage::PreGame {
+            self.spectate(server, client_id, name);
+            return Ok(());
+        }
+
+        let player_id = self.game_state.generate_id();
+        let spawn_coord = self.game_state.random_spawn();
+        let event = GameEvent::PlayerJoined {
+            player_id,
+            name: name.clone(),
+            position: spawn_coord.clone(),
+            client_id: client_id.raw(),
+        };
+        println!("🟢 {} [{}] joined {}.", name, player_id, self.name);
+        self.broadcast(server, &event);
+        self.clients.insert(client_id);
+
+        self.send(server, client_id, &GameEvent::RoomJoined { room: self.info() });
+        let id_event = GameEvent::Spawn {
+            player_id,
+            name,
+            position: spawn_coord,
+            lvl: self.game_state.lvl,
+            checksum: self.game_state.maze.checksum(),
+        };
+        self.send(server, client_id, &id_event);
+
+        self.game_state.consume(&event, client_id.raw());
+        self.broadcast(server, &self.game_state.lobby_event());
+        Ok(())
+    }
+
+    /// Lets a client that came too late watch the game, it plays the next round
+    fn spectate(&mut self, server: &mut RenetServer, client_id: ClientId, name: String) {
+        println!("👀 {} is watching {}.", name, self.name);
+        self.game_state.add_spectator(client_id.raw(), name);
+        self.clients.insert(client_id);
+
+        self.send(server, client_id, &GameEvent::RoomJoined { room: self.info() });
+        let event = GameEvent::Spectate {
+            lvl: self.game_state.lvl,
+            checksum: self.game_state.maze.checksum(),
+            player_list: self.game_state.players.clone(),
+        };
+        self.send(server, client_id, &event);
+        self.send(server, client_id, &GameEvent::Scores { scores: self.game_state.scores() });
+    }
+
+    pub fn leave(&mut self, server: &mut RenetServer, client_id: ClientId, reason: &str) {
+        self.clients.remove(&client_id);
+        if let Some(name) = self.game_state.spectators.remove(&client_id.raw()) {
+            println!("🔻 Spectator {} left {} due to \"{}\"", name, self.name, reason);
+            return;
+        }
+        let player_id = self.game_state.get_player_id(client_id.raw());
+        let name = self.game_state.players
+            .get(&player_id)
+            .map(|player| player.name.clone())
+            .unwrap_or_default();
+        // First consume a disconnect event
+        let event = GameEvent::PlayerDisconnected { player_id };
+        self.game_state.consume(&event, client_id.raw());
+        self.broadcast(server, &event);
+        println!(
+            "🔻 Player {} [{}] left {} due to \"{}\"",
+            name,
+            player_id,
+            self.name,
+            reason
+        );
+        if self.game_state.stage == Stage::PreGame {
+            self.broadcast(server, &self.game_state.lobby_event());
+        }
+    }
+
+    /// Validates and applies an event sent by one of the clients of the room,
+    /// then sends the outcome to whoever needs it
+    pub fn handle_message(
+        &mut self,
+        server: &mut RenetServer,
+        client_id: ClientId,
+        mut event: GameEvent
+    ) {
+        if !self.game_state.validate(&event, client_id.raw()) {
+            return;
+        }
+        // the shot was fired about half a round trip ago, rewind by rtt/2 rather than
+        // trusting the clock of the client. renet measures the round trip in seconds
+        if let GameEvent::Fire { .. } = event {
+            let rtt = server
+                .network_info(client_id)
+                .ok()
+                .and_then(|info| Duration::try_from_secs_f64(info.rtt).ok())
+                .unwrap_or_default();
+            stamp_shot(&mut event, rtt);
+        }
+        // never trust the position sent by a client
+        if let GameEvent::PlayerMove { at, .. } = &mut event {
+            let allowed = self.game_state.correct_move(client_id.raw(), at);
+            if allowed != *at {
+                let correction = GameEvent::PositionCorrection {
+                    position: allowed.clone(),
+                };
+                self.send(server, client_id, &correction);
+                *at = allowed;
+            }
+        }
+        let broad_event = self.game_state.consume(&event, client_id.raw());
+        match broad_event {
+            GameEvent::PlayerMove { .. } => {
+                self.send_player_lists(server, false);
+            }
+            GameEvent::Impact { id, lives, attacker } => {
+                self.broadcast(server, &broad_event);
+                println!("💥 Player [{}] was hit by [{}], {} lives left", id, attacker, lives);
+                // the server alone decides who dies
+                for player_id in self.game_state.dead_players() {
+                    let kill = GameEvent::Kill {
+                        killer: attacker,
+                        victim: player_id,
+                        weapon: WeaponKind::default(),
+                    };
+                    self.broadcast(server, &kill);
+                    let event = self.game_state.death_of(player_id);
+                    self.game_state.consume(&event, client_id.raw());
+                    self.broadcast(server, &event);
+                    println!("☠️ Player [{}] died", player_id);
+                }
+                let scores = GameEvent::Scores { scores: self.game_state.scores() };
+                self.broadcast(server, &scores);
+            }
+            GameEvent::Fire { .. } => {
+                // missed shots are not worth sending
+            }
+            GameEvent::MazeData { checksum, .. } => {
+                println!("🔁 resending maze {:x} to client {}", checksum, client_id);
+                self.send(server, client_id, &broad_event);
+            }
+            _ => {
+                self.broadcast(server, &broad_event);
+            }
+        }
+    }
+
+    /// Ends the game in progress and announces the level of the next round
+    pub fn end(&mut self, server: &mut RenetServer, reason: EndGameReason, levels: &[Maze]) {
+        let event = GameEvent::EndGame { reason };
+        self.game_state.consume(&event, u64::MAX);
+        self.broadcast(server, &event);
+        match reason.winner() {
+            Some(id) => {
+                println!("✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨");
+                println!("✨                                                  ✨");
+                println!("✨               Player [{}] has won !              ✨", id);
+                println!("✨                                                  ✨");
+                println!("✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨✨");
+            }
+            None => println!("🤝 Nobody won ({:?})", reason),
+        }
+        println!("🟥 Game has ended in {}", self.name);
+
+        let lvl = self.rotation.peek().unwrap_or(self.game_state.lvl);
+        let event = GameEvent::NextRound {
+            lvl,
+            level: levels[lvl - 1].name.clone(),
+            in_secs: self.game_state.next_round_in(),
+        };
+        self.broadcast(server, &event);
+    }
+
+    /// Sends everyone back to the lobby right away, on the given level
+    pub fn restart(&mut self, server: &mut RenetServer, lvl: usize, maze: Maze) {
+        if self.game_state.stage == Stage::InGame {
+            let event = GameEvent::EndGame { reason: EndGameReason::Stopped };
+            self.game_state.consume(&event, u64::MAX);
+            self.broadcast(server, &event);
+        }
+        println!("🔁 restarting {} on level {} ({})", self.name, lvl, maze.name);
+        self.game_state.next_round(lvl, maze);
+    }
+
+    /// Runs the lobby countdown, the respawns, the end of the game and the next round.
+    /// `levels` are the mazes the rotation picks from. Returns the spectators that
+    /// couldn't play the next round, they left the room and may pick another one
+    pub fn update(&mut self, server: &mut RenetServer, levels: &[Maze]) -> Vec<ClientId> {
+        let mut refused = Vec::new();
+        if self.game_state.stage == Stage::InGame {
+            // bring back the dead players of a deathmatch at a safe spot
+            for player_id in self.game_state.due_respawns() {
+                let event = GameEvent::Respawn {
+                    player_id,
+                    position: self.game_state.random_spawn(),
+                };
+                self.game_state.consume(&event, u64::MAX);
+                self.broadcast(server, &event);
+                println!("🔄 Player [{}] respawned", player_id);
+            }
+
+            // ^Determine if the game is over: winner, frag limit or time limit
+            if let Some(reason) = self.game_state.end_reason() {
+                self.end(server, reason, levels);
+            }
+        }
+
+        // after the results, everyone goes back to the lobby on the next level
+        if self.game_state.next_round_due() {
+            let lvl = self.rotation.next_level().unwrap_or(self.game_state.lvl);
+            let maze = levels[lvl - 1].clone();
+            println!("🔁 next round of {} on level {} ({})", self.name, lvl, maze.name);
+            self.game_state.next_round(lvl, maze);
+        }
+
+        // the spectators play as soon as the room is back in the lobby
+        if self.game_state.stage == Stage::PreGame && !self.game_state.spectators.is_empty() {
+            for (client_id, name) in self.game_state.take_spectators() {
+                let client_id = ClientId::from_raw(client_id);
+                if let Err(reason) = self.join(server, client_id, &name) {
+                    println!("❌ {} can't play in {}: {}", name, self.name, reason);
+                    self.clients.remove(&client_id);
+                    self.send(server, client_id, &GameEvent::RoomRefused { reason });
+                    refused.push(client_id);
+                }
+            }
+        }
+
+        if self.game_state.stage == Stage::PreGame && self.timer.elapsed() > Duration::from_secs(1) {
+            self.timer = Instant::now();
+            match self.game_state.tick_lobby() {
+                LobbyTick::Waiting => {}
+                LobbyTick::Countdown(duration) => {
+                    self.broadcast(server, &GameEvent::Timer { duration });
+                }
+                LobbyTick::Cancelled => {
+                    println!("⏸ countdown cancelled in {}, not enough players are ready", self.name);
+                    self.broadcast(server, &self.game_state.lobby_event());
+                }
+                LobbyTick::Start => {
+                    println!("game has started in {}", self.name);
+                    let event = GameEvent::BeginGame {
+                        player_list: self.game_state.players.clone(),
+                    };
+                    self.game_state.consume(&event, u64::MAX); //sets game stage to InGame
+                    self.send_player_lists(server, true);
+                }
+            }
+        }
+        refused
+    }
+}
+
+/// The rooms of the server, oldest first
+pub fn room_list(rooms: &HashMap<u32, Room>) -> GameEvent {
+    let mut rooms: Vec<RoomInfo> = rooms.values().map(Room::info).collect();
+    rooms.sort_by_key(|room| room.id);
+    GameEvent::RoomList { rooms }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use renet::ConnectionConfig;
+
+    /// A room in its lobby with two players, and a spectator waiting for the next round
+    fn room_with_spectator(max_players: usize) -> (Room, RenetServer) {
+        let mut server = RenetServer::new(ConnectionConfig::default());
+        for raw in 1..=3 {
+            server.add_connection(ClientId::from_raw(raw));
+        }
+        let config = Config { max_players: 3, ..Config::default() };
+        let mut room = Room::new(
+            1,
+            "Room".to_string(),
+            1,
+            Maze::level(1),
+            GameMode::default(),
+            Rotation::after(1, BUILTIN_LEVELS),
+            &config
+        );
+        room.join(&mut server, ClientId::from_raw(1), "neo").unwrap();
+        room.join(&mut server, ClientId::from_raw(2), "trinity").unwrap();
+        room.game_state.add_spectator(3, "morpheus".to_string());
+        room.clients.insert(ClientId::from_raw(3));
+        room.max_players = max_players;
+        (room, server)
+    }
+
+    #[test]
+    fn spectators_play_the_next_round() {
+        let (mut room, mut server) = room_with_spectator(3);
+        assert!(room.update(&mut server, &[Maze::level(1)]).is_empty());
+        assert!(room.game_state.spectators.is_empty());
+        assert_eq!(room.game_state.players.len(), 3);
+        assert_eq!(room.clients.len(), 3);
+    }
+
+    #[test]
+    fn spectators_refused_by_a_full_room_leave_it() {
+        let (mut room, mut server) = room_with_spectator(2);
+        let refused = room.update(&mut server, &[Maze::level(1)]);
+        assert_eq!(refused, vec![ClientId::from_raw(3)]);
+        assert!(room.game_state.spectators.is_empty());
+        assert_eq!(room.game_state.players.len(), 2);
+        assert!(!room.clients.contains(&ClientId::from_raw(3)));
+    }
+}
@@ -0,0 +1,138 @@
+//! Connect tokens for the secure mode of the server.
+//!
+//! A client asks for a token over TCP on the token port, by default `TOKEN_PORT_OFFSET`
+//! ports after the game port: it sends its username on one line, and gets back a status
+//! byte followed by either a netcode `ConnectToken` (status 0) or the reason of the
+//! refusal in UTF-8 (status 1).
+//! The token is signed with the private key of the server, so a client can neither pick
+//! its `client_id` nor change its name once it has one.
+
+use renet::transport::{ ConnectToken, NETCODE_KEY_BYTES };
+use std::io::{ BufRead, BufReader, Read, Write };
+use std::net::{ SocketAddr, TcpListener, TcpStream };
+use std::path::Path;
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use std::sync::Arc;
+use std::thread::{ self, JoinHandle };
+use std::time::{ Duration, SystemTime };
+use std::fs;
+use store::{ validate_name, PROTOCOL_ID };
+
+use crate::{ user_data_from_name, ConfigError };
+
+/// How long a token can be used to connect, in seconds
+pub const TOKEN_EXPIRE_SECONDS: u64 = 300;
+/// Seconds without news from a client before the connection is dropped
+pub const TOKEN_TIMEOUT_SECONDS: i32 = 15;
+/// How long a client asking for a token may take to send its name or read the answer
+pub const TOKEN_IO_TIMEOUT: Duration = Duration::from_secs(2);
+/// Token requests served at the same time, the others are dropped
+pub const TOKEN_MAX_PENDING: usize = 32;
+
+/// Reads the private key shared by the server and the token issuer, 64 hexadecimal
+/// digits. A missing file gets a new random key
+pub fn load_or_create_key(path: &Path) -> Result<[u8; NETCODE_KEY_BYTES], ConfigError> {
+    if !path.exists() {
+        let key: [u8; NETCODE_KEY_BYTES] = rand::random();
+        let hex: String = key
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        fs::write(path, hex + "\n").map_err(|e| {
+            ConfigError(format!("cannot write {}: {}", path.display(), e))
+        })?;
+        println!("🔑 created a new private key in {}", path.display());
+        return Ok(key);
+    }
+    let text = fs
+        ::read_to_string(path)
+        .map_err(|e| ConfigError(format!("cannot read {}: {}", path.display(), e)))?;
+    let text = text.trim();
+    let invalid = || {
+        ConfigError(
+            format!("{} must hold {} hexadecimal digits", path.display(), NETCODE_KEY_BYTES * 2)
+        )
+    };
+    if text.len() != NETCODE_KEY_BYTES * 2 || !text.is_ascii() {
+        return Err(invalid());
+    }
+    let mut key = [0u8; NETCODE_KEY_BYTES];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&text[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(key)
+}
+
+/// A token letting a new client with this name connect to `server_addr`
+pub fn issue_token(
+    name: &str,
+    server_addr: SocketAddr,
+    private_key: &[u8; NETCODE_KEY_BYTES]
+) -> Result<ConnectToken, String> {
+    let name = validate_name(name).map_err(|e| e.to_string())?;
+    let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap();
+    ConnectToken::generate(
+        current_time,
+        PROTOCOL_ID,
+        TOKEN_EXPIRE_SECONDS,
+        rand::random(),
+        TOKEN_TIMEOUT_SECONDS,
+        vec![server_addr],
+        Some(&user_data_from_name(&name)),
+        private_key
+    ).map_err(|e| e.to_string())
+}
+
+fn answer(mut stream: TcpStream, server_addr: SocketAddr, private_key: &[u8; NETCODE_KEY_BYTES]) {
+    let peer = stream
+        .peer_addr()
+        .map(|addr| addr.to_string())
+        .unwrap_or_default();
+    let _ = stream.set_read_timeout(Some(TOKEN_IO_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(TOKEN_IO_TIMEOUT));
+    let mut name = String::new();
+    if BufReader::new((&stream).take(256)).read_line(&mut name).is_err() {
+        return;
+    }
+    let name = name.trim();
+    let result = match issue_token(name, server_addr, private_key) {
+        Ok(token) => {
+            println!("🔑 issued a token for {} to {}", name, peer);
+            stream.write_all(&[0]).and_then(|_| token.write(&mut stream))
+        }
+        Err(reason) => {
+            println!("❌ refused a token to {}: {}", peer, reason);
+            stream.write_all(&[1]).and_then(|_| stream.write_all(reason.as_bytes()))
+        }
+    };
+    if let Err(e) = result {
+        println!("❌ cannot send a token to {}: {}", peer, e);
+    }
+}
+
+/// Serves connect tokens for `server_addr` on `addr`, from a thread of its own. Each request
+/// gets a thread too, so that a slow client doesn't hold up the others
+pub fn spawn_token_issuer(
+    addr: SocketAddr,
+    server_addr: SocketAddr,
+    private_key: [u8; NETCODE_KEY_BYTES]
+) -> std::io::Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(addr)?;
+    println!("🔑 issuing connect tokens on {}", addr);
+    Ok(
+        thread::spawn(move || {
+            let pending = Arc::new(AtomicUsize::new(0));
+            for stream in listener.incoming().flatten() {
+                if pending.fetch_add(1, Ordering::SeqCst) >= TOKEN_MAX_PENDING {
+                    pending.fetch_sub(1, Ordering::SeqCst);
+                    continue;
+                }
+                let pending = pending.clone();
+                thread::spawn(move || {
+                    answer(stream, server_addr, &private_key);
+                    pending.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        })
+    )
+}
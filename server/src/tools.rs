@@ -1,15 +1,37 @@
 use renet::transport::NETCODE_USER_DATA_BYTES;
 use std::io::*;
+use std::path::Path;
+use store::{
+    load_maps_dir,
+    load_maze,
+    GameMode,
+    Maze,
+    NameError,
+    Rotation,
+    BUILTIN_LEVELS,
+};
+
+use crate::{ Config, ConfigError };
 /// Utility function for extracting a player name from renet user data
 
-pub fn name_from_user_data(user_data: &[u8; NETCODE_USER_DATA_BYTES]) -> String {
+pub fn name_from_user_data(user_data: &[u8; NETCODE_USER_DATA_BYTES]) -> Result<String, NameError> {
     let mut buffer = [0u8; 8];
     buffer.copy_from_slice(&user_data[0..8]);
     let mut len = u64::from_le_bytes(buffer) as usize;
     len = len.min(NETCODE_USER_DATA_BYTES - 8);
     let data = user_data[8..len + 8].to_vec();
-    String::from_utf8(data).unwrap()
+    String::from_utf8(data).map_err(|_| NameError::InvalidUtf8)
+}
+
+/// The user data carrying the name of a player, read back by `name_from_user_data`
+pub fn user_data_from_name(name: &str) -> [u8; NETCODE_USER_DATA_BYTES] {
+    let mut user_data = [0u8; NETCODE_USER_DATA_BYTES];
+    let len = name.len().min(NETCODE_USER_DATA_BYTES - 8);
+    user_data[0..8].copy_from_slice(&(len as u64).to_le_bytes());
+    user_data[8..8 + len].copy_from_slice(&name.as_bytes()[..len]);
+    user_data
 }
+
 pub fn get_input(prompt: &str) -> String {
     print!("{}", prompt);
     stdout().flush().unwrap();
@@ -18,21 +40,59 @@ pub fn get_input(prompt: &str) -> String {
     input.trim().to_string()
 }
 
-pub fn get_level() -> usize {
+/// Custom levels found in `dir`, invalid files are reported and skipped
+pub fn custom_levels(dir: &Path) -> Vec<Maze> {
+    let mut mazes = Vec::new();
+    for (path, maze) in load_maps_dir(dir) {
+        match maze {
+            Ok(maze) => mazes.push(maze),
+            Err(e) => println!("❌ skipping map {}:{}", path.display(), e),
+        }
+    }
+    mazes
+}
+
+/// Every level rooms can be played on, the built-in levels come first so that level
+/// `lvl` is at index `lvl - 1`. With a seed they are generated, the clients download them
+pub fn all_levels(maps_dir: &Path, seed: Option<u64>) -> Vec<Maze> {
+    let mut levels: Vec<Maze> = (1..=BUILTIN_LEVELS)
+        .map(|lvl| match seed {
+            Some(seed) => Maze::generated_level(lvl, seed),
+            None => Maze::level(lvl),
+        })
+        .collect();
+    levels.extend(custom_levels(maps_dir));
+    levels
+}
+
+/// Difficulty of each built-in level, shown when picking a level
+const DIFFICULTIES: [&str; BUILTIN_LEVELS] = ["Easy", "Medium", "Hard"];
+
+/// Asks for a level, built-in levels come first followed by the custom ones
+pub fn get_level(levels: &[Maze]) -> (usize, Maze) {
     println!("######### MULTIPLAYER-FPS: MAZE WARS #########");
     println!("Welcome warrior !");
-    let message = "Pick a level:\n1. lvl 1 (Easy)\n2. lvl 2 (Medium)\n3. lvl 3 (Hard)\n>";
+    let mut message = "Pick a level:\n".to_string();
+    for (i, maze) in levels.iter().enumerate() {
+        let line = match DIFFICULTIES.get(i) {
+            Some(difficulty) => format!("{}. lvl {} ({})\n", i + 1, i + 1, difficulty),
+            None => format!("{}. {} (Custom)\n", i + 1, maze.name),
+        };
+        message.push_str(&line);
+    }
+    message.push('>');
+    let max = levels.len();
     let mut choice = 0;
     let mut ok = false;
     while !ok {
-        let data = get_input(message).parse::<usize>();
+        let data = get_input(&message).parse::<usize>();
         if data.is_err() {
             println!("❌ invalid input, please enter a valid number");
             continue;
         }
         choice = data.unwrap();
-        if choice < 1 || choice > 3 {
-            println!("❌ invalid input, Please pick a number between 1, 2 & 3 ");
+        if choice < 1 || choice > max {
+            println!("❌ invalid input, Please pick a number between 1 & {} ", max);
             continue;
         } else {
             ok = true;
@@ -40,7 +100,96 @@ pub fn get_level() -> usize {
     }
     print!("\x1B[2J\x1B[H");
     stdout().flush().unwrap();
-    choice
+    (choice, levels[choice - 1].clone())
+}
+
+/// Asks for the rules of the game
+pub fn get_mode() -> GameMode {
+    loop {
+        let choice = get_input(
+            "Pick a game mode:\n1. Last man standing\n2. Deathmatch (respawns, frag & time limit)\n>"
+        );
+        match choice.as_str() {
+            "1" => {
+                return GameMode::LastManStanding;
+            }
+            "2" => {
+                return GameMode::deathmatch();
+            }
+            _ => println!("❌ invalid input, please enter 1 or 2"),
+        }
+    }
+}
+
+/// Asks for the levels played after `lvl`, one round each
+pub fn get_rotation(lvl: usize, count: usize) -> Rotation {
+    loop {
+        let input = get_input(
+            "Level rotation, e.g. 2,3,1 (Enter to play every level in turn):\n>"
+        );
+        if input.is_empty() {
+            return Rotation::after(lvl, count);
+        }
+        let levels: Option<Vec<usize>> = input
+            .split(',')
+            .map(|level| level.trim().parse::<usize>().ok())
+            .map(|level| level.filter(|level| (1..=count).contains(level)))
+            .collect();
+        match levels {
+            Some(levels) => {
+                return Rotation::new(levels);
+            }
+            None => println!("❌ invalid input, please list levels between 1 & {}", count),
+        }
+    }
+}
+
+/// The level of the main room: the map file or the level of the config, or the one
+/// picked on stdin. A map file that isn't in the maps directory is added to the levels
+pub fn configured_level(
+    config: &Config,
+    levels: &mut Vec<Maze>
+) -> Result<(usize, Maze), ConfigError> {
+    if let Some(path) = &config.map {
+        let maze = load_maze(path).map_err(|e| {
+            ConfigError(format!("invalid map {}: {}", path.display(), e))
+        })?;
+        let lvl = match levels.iter().position(|level| *level == maze) {
+            Some(index) => index + 1,
+            None => {
+                levels.push(maze.clone());
+                levels.len()
+            }
+        };
+        return Ok((lvl, maze));
+    }
+    match config.level {
+        Some(lvl) if (1..=levels.len()).contains(&lvl) => Ok((lvl, levels[lvl - 1].clone())),
+        Some(lvl) => {
+            let message = format!("there is no level {}, pick one between 1 & {}", lvl, levels.len());
+            Err(ConfigError(message))
+        }
+        None => Ok(get_level(levels)),
+    }
+}
+
+/// The rotation of the config, asked on stdin when nothing else was configured
+pub fn configured_rotation(
+    config: &Config,
+    lvl: usize,
+    count: usize
+) -> Result<Rotation, ConfigError> {
+    match &config.rotation {
+        Some(levels) => {
+            config.check_rotation(count)?;
+            Ok(Rotation::new(levels.clone()))
+        }
+        None if config.is_interactive() => Ok(get_rotation(lvl, count)),
+        None => Ok(Rotation::after(lvl, count)),
+    }
 }
 
+/// Default number of players in a room
 pub const PLAYER_LIMIT: usize = 10;
+/// Default number of rooms hosted at the same time
+pub const MAX_ROOMS: usize = 8;
@@ -0,0 +1,207 @@
+use crate::*;
+use serde::{ Deserialize, Serialize };
+use std::collections::VecDeque;
+
+/// Metrics describing the layout (and so the difficulty) of a maze
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MazeReport {
+    /// Groups of cells that can reach each other, 1 means everything is reachable
+    pub components: usize,
+    /// Number of cells in the biggest group
+    pub largest_component: usize,
+    /// Cells with a single opening
+    pub dead_ends: usize,
+    /// The longest of all shortest paths between two reachable cells, in cells
+    pub longest_path: usize,
+    /// Mean length of the runs of cells having exactly two openings
+    pub average_corridor: f32,
+}
+
+/// Labels every cell with the index of its connected component
+pub fn component_labels(maze: &Maze) -> Vec<Vec<usize>> {
+    let mut labels = vec![vec![usize::MAX; maze.cols()]; maze.rows()];
+    let mut next = 0;
+    for row in 0..maze.rows() {
+        for col in 0..maze.cols() {
+            if labels[row][col] != usize::MAX {
+                continue;
+            }
+            labels[row][col] = next;
+            let mut queue = VecDeque::from([(row, col)]);
+            while let Some(cell) = queue.pop_front() {
+                for (r, c) in maze.open_neighbours(cell) {
+                    if labels[r][c] == usize::MAX {
+                        labels[r][c] = next;
+                        queue.push_back((r, c));
+                    }
+                }
+            }
+            next += 1;
+        }
+    }
+    labels
+}
+
+/// Shortest path length (in steps) from a cell to every other one, `None` when unreachable
+pub fn distances_from(maze: &Maze, start: (usize, usize)) -> Vec<Vec<Option<usize>>> {
+    let mut dist = vec![vec![None; maze.cols()]; maze.rows()];
+    if start.0 >= maze.rows() || start.1 >= maze.cols() {
+        return dist;
+    }
+    dist[start.0][start.1] = Some(0);
+    let mut queue = VecDeque::from([start]);
+    while let Some(cell) = queue.pop_front() {
+        let d = dist[cell.0][cell.1].unwrap_or(0);
+        for (r, c) in maze.open_neighbours(cell) {
+            if dist[r][c].is_none() {
+                dist[r][c] = Some(d + 1);
+                queue.push_back((r, c));
+            }
+        }
+    }
+    dist
+}
+
+/// Whether all the given world positions are inside the maze and reachable from each other
+pub fn all_reachable(maze: &Maze, positions: &[Position]) -> bool {
+    let labels = component_labels(maze);
+    let mut component = None;
+    for position in positions {
+        let (row, col) = match maze.cell_at(position.x, position.z) {
+            Some(cell) => cell,
+            None => {
+                return false;
+            }
+        };
+        match component {
+            None => {
+                component = Some(labels[row][col]);
+            }
+            Some(label) if label != labels[row][col] => {
+                return false;
+            }
+            _ => {}
+        }
+    }
+    true
+}
+
+/// Computes the metrics of a maze
+pub fn analyze(maze: &Maze) -> MazeReport {
+    let labels = component_labels(maze);
+    let mut sizes: Vec<usize> = Vec::new();
+    let mut dead_ends = 0;
+    let mut corridor_cells = Vec::new();
+    for (row, line) in labels.iter().enumerate() {
+        for (col, &label) in line.iter().enumerate() {
+            if sizes.len() <= label {
+                sizes.resize(label + 1, 0);
+            }
+            sizes[label] += 1;
+            match maze.open_neighbours((row, col)).len() {
+                1 => {
+                    dead_ends += 1;
+                }
+                2 => corridor_cells.push((row, col)),
+                _ => {}
+            }
+        }
+    }
+
+    let mut longest_path = 0;
+    for row in 0..maze.rows() {
+        for col in 0..maze.cols() {
+            let farthest = distances_from(maze, (row, col))
+                .into_iter()
+                .flatten()
+                .flatten()
+                .max()
+                .unwrap_or(0);
+            longest_path = longest_path.max(farthest);
+        }
+    }
+
+    // chains of two-way cells joined by openings form a single corridor
+    let mut seen = vec![vec![false; maze.cols()]; maze.rows()];
+    let mut corridors = 0;
+    for &(row, col) in &corridor_cells {
+        if seen[row][col] {
+            continue;
+        }
+        corridors += 1;
+        seen[row][col] = true;
+        let mut stack = vec![(row, col)];
+        while let Some(cell) = stack.pop() {
+            for (r, c) in maze.open_neighbours(cell) {
+                if !seen[r][c] && maze.open_neighbours((r, c)).len() == 2 {
+                    seen[r][c] = true;
+                    stack.push((r, c));
+                }
+            }
+        }
+    }
+    let average_corridor = if corridors == 0 {
+        0.0
+    } else {
+        (corridor_cells.len() as f32) / (corridors as f32)
+    };
+
+    MazeReport {
+        components: sizes.len(),
+        largest_component: sizes.iter().copied().max().unwrap_or(0),
+        dead_ends,
+        longest_path,
+        average_corridor,
+    }
+}
+
+/// Problems that make a maze unfit to be played, empty when it is fine
+pub fn maze_issues(maze: &Maze) -> Vec<String> {
+    let mut issues = Vec::new();
+    let report = analyze(maze);
+    if report.components > 1 {
+        issues.push(format!("{} areas can't be reached from each other", report.components));
+    }
+    let spawns = get_spawn_positions(maze);
+    if spawns.len() < 2 {
+        issues.push("at least 2 spawn points are needed".to_string());
+    } else if !all_reachable(maze, &spawns) {
+        issues.push("some spawn points can't reach each other".to_string());
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_levels_get_harder() {
+        let dead_ends: Vec<usize> = (1..=3).map(|lvl| analyze(&Maze::level(lvl)).dead_ends).collect();
+        assert!(dead_ends[0] < dead_ends[1], "Map1 vs Map2: {:?}", dead_ends);
+        assert!(dead_ends[1] < dead_ends[2], "Map2 vs Map3: {:?}", dead_ends);
+    }
+
+    #[test]
+    fn builtin_spawns_are_reachable() {
+        for lvl in 1..=3 {
+            let maze = Maze::level(lvl);
+            assert_eq!(analyze(&maze).components, 1, "Map{} has unreachable cells", lvl);
+            assert!(all_reachable(&maze, &get_spawn_positions(&maze)), "Map{}", lvl);
+        }
+    }
+
+    #[test]
+    fn generated_levels_get_harder() {
+        for seed in 0..10 {
+            let reports: Vec<MazeReport> = (1..=3)
+                .map(|lvl| analyze(&Maze::generate(&MazeParams::for_level(lvl, seed))))
+                .collect();
+            for report in &reports {
+                assert_eq!(report.components, 1, "seed {}", seed);
+            }
+            assert!(reports[0].dead_ends <= reports[1].dead_ends, "seed {}", seed);
+            assert!(reports[1].dead_ends < reports[2].dead_ends, "seed {}", seed);
+        }
+    }
+}
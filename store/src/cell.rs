@@ -0,0 +1,165 @@
+use serde::{ Deserialize, Serialize };
+use std::ops::{ BitOr, BitOrAssign };
+
+/*
+    Legacy encoding: one code per wall corner, a maze of N x M cells
+    is therefore described by a (N + 1) x (M + 1) grid.
+    1 Vers le bas
+    2 Fin de ligne
+    3 vers la droite
+    4 Vers le bas et la droite
+*/
+pub const WALL_DOWN: u8 = 1;
+pub const WALL_NONE: u8 = 2;
+pub const WALL_RIGHT: u8 = 3;
+pub const WALL_DOWN_RIGHT: u8 = 4;
+
+/// The walls around a single cell, one bit per side.
+/// A wall shared by two cells is set on both of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CellWalls(pub u8);
+
+impl CellWalls {
+    pub const NONE: Self = Self(0);
+    pub const TOP: Self = Self(1);
+    pub const RIGHT: Self = Self(1 << 1);
+    pub const BOTTOM: Self = Self(1 << 2);
+    pub const LEFT: Self = Self(1 << 3);
+    pub const ALL: Self = Self(0b1111);
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub fn contains(self, side: Self) -> bool {
+        self.0 & side.0 == side.0
+    }
+
+    pub fn insert(&mut self, side: Self) {
+        self.0 |= side.0;
+    }
+
+    pub fn remove(&mut self, side: Self) {
+        self.0 &= !side.0;
+    }
+
+    pub fn set(&mut self, side: Self, wall: bool) {
+        if wall {
+            self.insert(side);
+        } else {
+            self.remove(side);
+        }
+    }
+
+    /// Number of sides without a wall
+    pub fn openings(self) -> usize {
+        4 - ((self.0 & Self::ALL.0).count_ones() as usize)
+    }
+}
+
+impl BitOr for CellWalls {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for CellWalls {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+fn legacy_down(grid: &[Vec<u8>], row: usize, col: usize) -> bool {
+    matches!(grid.get(row).and_then(|r| r.get(col)), Some(&WALL_DOWN) | Some(&WALL_DOWN_RIGHT))
+}
+
+fn legacy_right(grid: &[Vec<u8>], row: usize, col: usize) -> bool {
+    matches!(grid.get(row).and_then(|r| r.get(col)), Some(&WALL_RIGHT) | Some(&WALL_DOWN_RIGHT))
+}
+
+/// Converts a legacy corner grid to per-cell walls
+pub fn cells_from_legacy(grid: &[Vec<u8>]) -> Vec<Vec<CellWalls>> {
+    let rows = grid.len().saturating_sub(1);
+    let cols = grid
+        .first()
+        .map(|row| row.len().saturating_sub(1))
+        .unwrap_or(0);
+    let mut cells = vec![vec![CellWalls::NONE; cols]; rows];
+    for (row, line) in cells.iter_mut().enumerate() {
+        for (col, walls) in line.iter_mut().enumerate() {
+            walls.set(CellWalls::TOP, legacy_right(grid, row, col));
+            walls.set(CellWalls::LEFT, legacy_down(grid, row, col));
+            walls.set(CellWalls::BOTTOM, legacy_right(grid, row + 1, col));
+            walls.set(CellWalls::RIGHT, legacy_down(grid, row, col + 1));
+        }
+    }
+    cells
+}
+
+/// Converts per-cell walls back to a legacy corner grid.
+/// A wall set on only one of the two cells it separates is kept.
+pub fn cells_to_legacy(cells: &[Vec<CellWalls>]) -> Vec<Vec<u8>> {
+    let rows = cells.len();
+    let cols = cells.first().map(|row| row.len()).unwrap_or(0);
+    let walls = |row: usize, col: usize| cells[row][col];
+    let mut grid = vec![vec![WALL_NONE; cols + 1]; rows + 1];
+    for (row, line) in grid.iter_mut().enumerate() {
+        for (col, code) in line.iter_mut().enumerate() {
+            let down =
+                row < rows &&
+                ((col < cols && walls(row, col).contains(CellWalls::LEFT)) ||
+                    (col > 0 && walls(row, col - 1).contains(CellWalls::RIGHT)));
+            let right =
+                col < cols &&
+                ((row < rows && walls(row, col).contains(CellWalls::TOP)) ||
+                    (row > 0 && walls(row - 1, col).contains(CellWalls::BOTTOM)));
+            *code = match (down, right) {
+                (true, true) => WALL_DOWN_RIGHT,
+                (true, false) => WALL_DOWN,
+                (false, true) => WALL_RIGHT,
+                (false, false) => WALL_NONE,
+            };
+        }
+    }
+    grid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_mazes;
+
+    #[test]
+    fn legacy_grids_convert_losslessly() {
+        for name in ["Map1", "Map2", "Map3"] {
+            let grid = get_mazes(name);
+            let cells = cells_from_legacy(&grid);
+            assert_eq!(cells.len(), grid.len() - 1);
+            assert_eq!(cells_to_legacy(&cells), grid, "{}", name);
+        }
+    }
+
+    #[test]
+    fn shared_walls_are_set_on_both_cells() {
+        let cells = cells_from_legacy(&get_mazes("Map3"));
+        for (row, line) in cells.iter().enumerate() {
+            for (col, walls) in line.iter().enumerate() {
+                if col + 1 < line.len() {
+                    assert_eq!(
+                        walls.contains(CellWalls::RIGHT),
+                        line[col + 1].contains(CellWalls::LEFT)
+                    );
+                }
+                if row + 1 < cells.len() {
+                    assert_eq!(
+                        walls.contains(CellWalls::BOTTOM),
+                        cells[row + 1][col].contains(CellWalls::TOP)
+                    );
+                }
+            }
+        }
+    }
+}
@@ -3,5 +3,33 @@ use std::time::Duration;
 const DESIRED_FPS: u64 = 60;
 
 pub const NBR_OF_LIVES: u8 = 10;
+/// Longest player name, in characters
+pub const MAX_NAME_LENGTH: usize = 16;
+/// Default number of players needed to start a game
+pub const MIN_PLAYERS: usize = 2;
+/// Default length of the lobby countdown, in seconds
+pub const LOBBY_COUNTDOWN: u8 = 10;
+/// How long a dead player waits before coming back in deathmatch
+pub const RESPAWN_DELAY: Duration = Duration::from_secs(3);
+/// Default number of kills that wins a deathmatch
+pub const FRAG_LIMIT: u32 = 10;
+/// Default length of a deathmatch
+pub const TIME_LIMIT: Duration = Duration::from_secs(5 * 60);
+/// How long the results stay on screen before the next round
+pub const RESULTS_DURATION: Duration = Duration::from_secs(10);
+/// Number of spawn points computed for a maze
+pub const SPAWN_POINTS: usize = 10;
 pub const GAME_FPS: Duration = Duration::from_millis(1000 / DESIRED_FPS);
 pub const PROTOCOL_ID: u64 = 1582;
+/// The connect tokens of a secure server are issued this many ports after its game port
+pub const TOKEN_PORT_OFFSET: u16 = 1;
+/// The remote admin console of a server listens this many ports after its game port
+pub const RCON_PORT_OFFSET: u16 = 2;
+/// Side of the square ground the maze stands on
+pub const ARENA_SIZE: f32 = 28.0;
+/// Walking speed of the players, in world units per second
+pub const PLAYER_SPEED: f32 = 5.0;
+/// Slack given to clients on the distance they may walk between two moves
+pub const MOVE_TOLERANCE: f32 = 1.5;
+/// Longest walk a player can save up by standing still, so that idling doesn't allow a teleport
+pub const MAX_MOVE_INTERVAL: Duration = Duration::from_millis(250);
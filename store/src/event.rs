@@ -8,13 +8,41 @@ pub enum GameEvent {
     BeginGame {
         player_list: HashMap<u8, Players>,
     },
-    EndGame,
+    EndGame {
+        reason: EndGameReason,
+    },
     AccessForbidden,
+    /// Sent by the server to everyone when a shot from `attacker` hits a player,
+    /// with the lives it has left
     Impact {
         id: u8,
+        lives: u8,
+        attacker: u8,
+    },
+    /// A shot as seen by the shooter, the server decides what it hits.
+    /// `direction` doesn't need to be normalized. `timestamp` is in ms since the UNIX epoch,
+    /// the server replaces it with its own estimate (see `stamp_shot`)
+    Fire {
+        origin: Position,
+        direction: Position,
+        timestamp: u64,
     },
+    /// Sent by the server when a player runs out of lives, `respawn_in` is the number
+    /// of seconds before the player comes back or None when the player is out of the game
     Death {
         player_id: u8,
+        respawn_in: Option<u8>,
+    },
+    /// Sent by the server to everyone for the kill feed, along with the victim's `Death`
+    Kill {
+        killer: u8,
+        victim: u8,
+        weapon: WeaponKind,
+    },
+    /// Sent by the server when a dead player comes back with all its lives
+    Respawn {
+        player_id: u8,
+        position: Position,
     },
     PlayerJoined {
         player_id: u8,
@@ -31,10 +59,96 @@ pub enum GameEvent {
         player_list: HashMap<u8, Players>,
         vision: (f32, f32),
     },
+    /// Sent to a joining player with the checksum of the maze being played. A client
+    /// that has no maze with this checksum asks for it with a `MazeRequest`, so that
+    /// custom and generated levels don't need to be known by the client.
+    /// `name` is the player's name as the server made it unique
     Spawn {
         player_id: u8,
+        name: String,
         position: Position,
         lvl: usize,
+        checksum: u64,
+    },
+    /// Sent to a client before disconnecting it because of its name
+    NameRejected {
+        reason: String,
+    },
+    /// Sent to a client whose last move went through a wall or was too fast
+    PositionCorrection {
+        position: Position,
+    },
+    /// Sent by a client that has no maze matching the checksum it received
+    MazeRequest,
+    /// The server's answer to a `MazeRequest`
+    MazeData {
+        maze: Maze,
+        checksum: u64,
+    },
+    /// Sent by the server to everyone when the scores change
+    Scores {
+        scores: HashMap<u8, Score>,
+    },
+    /// Sent by the server to everyone right after `EndGame`, with the level of the
+    /// round that begins in `in_secs` seconds
+    NextRound {
+        lvl: usize,
+        level: String,
+        in_secs: u8,
+    },
+    /// Sent by the server to a client right before disconnecting it
+    Kicked {
+        reason: String,
+    },
+    /// A message from the server admins, shown to every player
+    ServerMessage {
+        text: String,
+    },
+    /// Sent by a client to get the rooms of the server
+    ListRooms,
+    /// The server's answer to `ListRooms`, also sent to every client when it connects
+    RoomList {
+        rooms: Vec<RoomInfo>,
+    },
+    /// Sent by a client that isn't in a room yet
+    JoinRoom {
+        room_id: u32,
+    },
+    /// Sent by a client that isn't in a room yet, the server's level and game mode
+    /// are used when not given
+    CreateRoom {
+        name: String,
+        lvl: Option<usize>,
+        mode: Option<GameMode>,
+    },
+    /// Sent by the server when a client enters a room, right before its `Spawn`
+    RoomJoined {
+        room: RoomInfo,
+    },
+    /// Sent by the server when a `JoinRoom` or `CreateRoom` can't be honoured
+    RoomRefused {
+        reason: String,
+    },
+    /// Sent instead of `Spawn` to a client joining a room whose game already began.
+    /// It watches the players until it gets a `Spawn` for the next round
+    Spectate {
+        lvl: usize,
+        checksum: u64,
+        player_list: HashMap<u8, Players>,
+    },
+    /// Sent by a player in the lobby to say whether it is ready to play
+    Ready {
+        ready: bool,
+    },
+    /// Sent by the host to start the countdown without waiting for everyone to be ready
+    ForceStart,
+    /// Sent by the server to everyone whenever the lobby changes, `countdown` is None
+    /// while the game isn't about to start
+    Lobby {
+        players: HashMap<u8, LobbyPlayer>,
+        host: Option<u8>,
+        min_players: usize,
+        countdown: Option<u8>,
     },
     Timer {
         duration: u8,
@@ -1,21 +1,107 @@
 use crate::*;
 use rand::*;
 use serde::{ Deserialize, Serialize };
-use std::{ collections::HashMap, u8 };
+use std::{ collections::{ HashMap, HashSet }, time::{ Duration, Instant } };
 
 /// The different states a game can be in. (not to be confused with the entire "GameState")
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Stage {
+    /// Players are in the lobby, getting ready
     PreGame,
     InGame,
     Ended,
 }
+/// The rules a game is played with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum GameMode {
+    /// Dead players are out, the last one alive wins
+    #[default]
+    LastManStanding,
+    /// Dead players come back after `RESPAWN_DELAY`, the game ends when someone
+    /// reaches `frag_limit` kills or after `time_limit` seconds
+    Deathmatch {
+        frag_limit: u32,
+        time_limit: u64,
+    },
+}
+
+impl GameMode {
+    pub fn deathmatch() -> Self {
+        GameMode::Deathmatch {
+            frag_limit: FRAG_LIMIT,
+            time_limit: TIME_LIMIT.as_secs(),
+        }
+    }
+}
+
 /// The reasons why a game could end
 #[derive(Debug, Clone, Copy, Serialize, PartialEq, Deserialize)]
 pub enum EndGameReason {
+    /// Every other player died or left
     PlayerWon {
-        winner: u64,
+        winner: u8,
+    },
+    FragLimit {
+        winner: u8,
     },
+    /// None when several players have the most kills
+    TimeLimit {
+        winner: Option<u8>,
+    },
+    /// An admin stopped the game
+    Stopped,
+}
+
+impl EndGameReason {
+    pub fn winner(&self) -> Option<u8> {
+        match self {
+            EndGameReason::PlayerWon { winner } | EndGameReason::FragLimit { winner } => {
+                Some(*winner)
+            }
+            EndGameReason::TimeLimit { winner } => *winner,
+            EndGameReason::Stopped => None,
+        }
+    }
+}
+
+/// How far a player may move: it fills up with time at the players' speed so that
+/// moves received in bursts are still accepted
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveBudget {
+    pub last: Instant,
+    pub distance: f32,
+}
+
+impl MoveBudget {
+    fn max() -> f32 {
+        PLAYER_SPEED * MAX_MOVE_INTERVAL.as_secs_f32() * MOVE_TOLERANCE
+    }
+
+    pub fn new() -> Self {
+        Self {
+            last: Instant::now(),
+            distance: Self::max(),
+        }
+    }
+
+    /// Adds the distance earned since the last refill, returns the available distance
+    pub fn refill(&mut self) -> f32 {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last).as_secs_f32();
+        self.last = now;
+        self.distance = (self.distance + PLAYER_SPEED * elapsed * MOVE_TOLERANCE).min(Self::max());
+        self.distance
+    }
+
+    pub fn spend(&mut self, distance: f32) {
+        self.distance = (self.distance - distance).max(0.0);
+    }
+}
+
+impl Default for MoveBudget {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// A GameState object that is able to keep track of game
@@ -26,7 +112,48 @@ pub struct GameState {
     pub history: Vec<GameEvent>,
     pub id_counter: u8,
     pub lvl: usize,
+    pub maze: Maze,
     pub spawn_positions: Vec<Position>,
+    #[serde(default)]
+    pub mode: GameMode,
+    #[serde(default)]
+    pub lobby: LobbyConfig,
+    /// Lives of a player when it spawns
+    #[serde(default = "default_lives")]
+    pub max_lives: u8,
+    /// The players of the lobby who are ready
+    #[serde(default)]
+    pub ready: HashSet<u8>,
+    /// The player allowed to force the start, the first one to join
+    #[serde(default)]
+    pub host: Option<u8>,
+    #[serde(default)]
+    pub forced_start: bool,
+    /// Seconds left before the game begins, None when the countdown isn't running
+    #[serde(default)]
+    pub countdown: Option<u8>,
+    /// Names of the clients watching the game, by client id. They play the next round
+    #[serde(default)]
+    pub spectators: HashMap<u64, String>,
+    /// When the game began, server side only
+    #[serde(skip)]
+    pub started_at: Option<Instant>,
+    /// When the game ended, server side only
+    #[serde(skip)]
+    pub ended_at: Option<Instant>,
+    /// When each dead player comes back, server side only
+    #[serde(skip)]
+    pub respawns: HashMap<u8, Instant>,
+    /// Distance each player may still walk, server side only
+    #[serde(skip)]
+    pub move_budgets: HashMap<u8, MoveBudget>,
+    /// Recent positions of each player, used to rewind shots. Server side only
+    #[serde(skip)]
+    pub histories: HashMap<u8, PositionHistory>,
+}
+
+fn default_lives() -> u8 {
+    NBR_OF_LIVES
 }
 
 impl Default for GameState {
@@ -37,7 +164,21 @@ impl Default for GameState {
             history: Vec::new(),
             id_counter: 0,
             lvl: 1,
-            spawn_positions: Vec::new(),
+            maze: Maze::level(1),
+            spawn_positions: get_spawn_positions(&Maze::level(1)),
+            mode: GameMode::default(),
+            lobby: LobbyConfig::default(),
+            max_lives: NBR_OF_LIVES,
+            ready: HashSet::new(),
+            host: None,
+            forced_start: false,
+            countdown: None,
+            spectators: HashMap::new(),
+            started_at: None,
+            ended_at: None,
+            respawns: HashMap::new(),
+            move_budgets: HashMap::new(),
+            histories: HashMap::new(),
         }
     }
 }
@@ -46,59 +187,74 @@ impl GameState {
     /// Determines whether an event is valid considering the current GameState
     pub fn validate(&self, event: &GameEvent, client_id: u64) -> bool {
         match event {
-            GameEvent::BeginGame { .. } => {
-                // Check that the game hasn't started yet. (we don't want to double start a game)
-                if self.stage != Stage::PreGame {
-                    return false;
-                }
+            GameEvent::BeginGame { .. } |
+            GameEvent::EndGame { .. } |
+            GameEvent::PlayerJoined { .. } |
+            GameEvent::PlayerDisconnected { .. } |
+            GameEvent::Spawn { .. } => {
+                // The server starts and ends the games, and lets players in and out
+                // through the lobby countdown, `end_reason` and the connections
+                return false;
             }
 
-            GameEvent::EndGame => {
-                // Check that the game has started before someone wins it
-                if self.stage != Stage::InGame {
+            GameEvent::PlayerMove { at: _, .. } => {
+                // dead players can't move until they respawn
+                if !self.is_alive(self.get_player_id(client_id)) {
                     return false;
                 }
             }
-
-            GameEvent::PlayerJoined { player_id, .. } => {
-                // Check that there isn't another player with the same id
-                if self.players.contains_key(player_id) || self.stage != Stage::PreGame {
+            GameEvent::Impact { .. } |
+            GameEvent::Death { .. } |
+            GameEvent::Kill { .. } |
+            GameEvent::Respawn { .. } => {
+                // Impacts, deaths, kills and respawns are decided by the server
+                return false;
+            }
+            GameEvent::Fire { .. } => {
+                if !self.is_alive(self.get_player_id(client_id)) || self.stage != Stage::InGame {
                     return false;
                 }
             }
-            GameEvent::PlayerDisconnected { player_id } => {
-                // Check player exists
-                if !self.players.contains_key(player_id) {
+            GameEvent::MazeRequest => {}
+            GameEvent::Ready { .. } => {
+                if self.stage != Stage::PreGame {
                     return false;
                 }
-            }
-
-            GameEvent::PlayerMove { at: _, .. } => {
-                let id = self.get_player_id(client_id);
-                if !self.players.contains_key(&id) && id != u8::MAX {
+                if !self.players.contains_key(&self.get_player_id(client_id)) {
                     return false;
                 }
             }
-            GameEvent::Spawn { .. } => {
-                if self.stage != Stage::PreGame {
+            GameEvent::ForceStart => {
+                let id = self.get_player_id(client_id);
+                if self.stage != Stage::PreGame || !self.is_host(id) {
                     return false;
                 }
-            }
-            GameEvent::Impact { id } => {
-                if !self.players.contains_key(id) {
+                if self.players.len() < self.lobby.min_players {
                     return false;
                 }
             }
 
-            GameEvent::Death { player_id } => {
-                if !self.players.contains_key(player_id) {
-                    println!("❌❌ dead player exists before remove");
-                    return false;
-                } else {
-                    println!("🟢 valid death");
-                }
+            GameEvent::AccessForbidden |
+            GameEvent::NameRejected { .. } |
+            GameEvent::PositionCorrection { .. } |
+            GameEvent::MazeData { .. } |
+            GameEvent::Scores { .. } |
+            GameEvent::NextRound { .. } |
+            GameEvent::Kicked { .. } |
+            GameEvent::ServerMessage { .. } |
+            GameEvent::RoomList { .. } |
+            GameEvent::RoomJoined { .. } |
+            GameEvent::RoomRefused { .. } |
+            GameEvent::Spectate { .. } |
+            GameEvent::Lobby { .. } |
+            GameEvent::Timer { .. } => {
+                // only the server sends these
+                return false;
+            }
+            GameEvent::ListRooms | GameEvent::JoinRoom { .. } | GameEvent::CreateRoom { .. } => {
+                // handled by the server before the client is in a room
+                return false;
             }
-            _ => unreachable!(),
         }
         true
     }
@@ -110,14 +266,16 @@ impl GameState {
         match valid_event {
             GameEvent::BeginGame { player_list } => {
                 self.stage = Stage::InGame;
+                self.started_at = Some(Instant::now());
                 eve = GameEvent::BeginGame {
                     player_list: player_list.clone(),
                 };
             }
 
-            GameEvent::EndGame => {
+            GameEvent::EndGame { reason } => {
                 self.stage = Stage::Ended;
-                eve = GameEvent::EndGame;
+                self.ended_at = Some(Instant::now());
+                eve = GameEvent::EndGame { reason: *reason };
             }
 
             GameEvent::PlayerJoined { player_id, name, position, client_id } => {
@@ -125,23 +283,35 @@ impl GameState {
                     name: name.to_string(),
                     id: *player_id,
                     position: position.clone(),
-                    client_id: client_id.clone(),
+                    client_id: *client_id,
                     vision: (0.0, 0.0),
-                    lives: 3,
+                    lives: self.max_lives,
+                    score: Score::default(),
                 });
+                self.histories.entry(*player_id).or_default().record(now_millis(), position.clone());
+                if self.host.is_none() {
+                    self.host = Some(*player_id);
+                }
 
                 eve = GameEvent::PlayerJoined {
                     player_id: *player_id,
                     name: name.to_string(),
                     position: position.clone(),
-                    client_id: client_id.clone(),
+                    client_id: *client_id,
                 };
             }
 
             GameEvent::PlayerDisconnected { player_id } => {
                 self.players.remove(player_id);
+                self.move_budgets.remove(player_id);
+                self.histories.remove(player_id);
+                self.respawns.remove(player_id);
+                self.ready.remove(player_id);
+                if self.is_host(*player_id) {
+                    self.host = self.players.keys().min().copied();
+                }
                 eve = GameEvent::PlayerDisconnected {
-                    player_id: player_id.clone(),
+                    player_id: *player_id,
                 };
             }
 
@@ -149,7 +319,8 @@ impl GameState {
                 let id = self.get_player_id(client_id);
                 let player = self.players.get_mut(&id).unwrap();
                 player.position = at.clone();
-                player.vision = vision.clone();
+                player.vision = *vision;
+                self.histories.entry(id).or_default().record(now_millis(), at.clone());
                 let mut player_list: HashMap<u8, Players> = HashMap::new();
                 for (idp, value) in self.players.clone() {
                     if !idp.eq(&id) {
@@ -160,17 +331,93 @@ impl GameState {
                     player_id: id,
                     at: at.clone(),
                     player_list,
-                    vision: vision.clone(),
+                    vision: *vision,
+                };
+            }
+            GameEvent::Impact { id, lives, attacker } => {
+                let previous = match self.players.get_mut(id) {
+                    Some(impacted_player) => {
+                        let previous = impacted_player.lives;
+                        impacted_player.lives = *lives;
+                        previous
+                    }
+                    None => {
+                        return valid_event.clone();
+                    }
                 };
+                if let Some(player) = self.players.get_mut(attacker) {
+                    player.score.damage += previous.saturating_sub(*lives) as u32;
+                    if *lives == 0 && previous > 0 {
+                        player.score.kills += 1;
+                    }
+                }
+                return valid_event.clone();
             }
-            GameEvent::Impact { id } => {
-                let impacted_player = self.players.get_mut(id).unwrap();
-                impacted_player.lives -= 1;
-                return GameEvent::Impact { id: id.clone() };
+            GameEvent::Fire { origin, direction, timestamp } => {
+                let shooter = self.get_player_id(client_id);
+                let hit = self.resolve_fire(shooter, origin, direction, *timestamp);
+                return match hit.and_then(|id| self.players.get(&id)) {
+                    Some(victim) => {
+                        let impact = GameEvent::Impact {
+                            id: victim.id,
+                            lives: victim.lives.saturating_sub(1),
+                            attacker: shooter,
+                        };
+                        self.consume(&impact, client_id)
+                    }
+                    None => valid_event.clone(),
+                };
             }
-            GameEvent::Death { player_id } => {
-                self.players.remove(player_id);
-                return GameEvent::Death { player_id: player_id.clone() };
+            GameEvent::Death { player_id, .. } => {
+                if let Some(player) = self.players.get_mut(player_id) {
+                    player.score.deaths += 1;
+                }
+                self.move_budgets.remove(player_id);
+                match self.mode {
+                    GameMode::LastManStanding => {
+                        // eliminated players watch the end of the game
+                        if let Some(player) = self.players.remove(player_id) {
+                            self.spectators.insert(player.client_id, player.name);
+                        }
+                        self.histories.remove(player_id);
+                    }
+                    GameMode::Deathmatch { .. } => {
+                        self.respawns.insert(*player_id, Instant::now() + RESPAWN_DELAY);
+                    }
+                }
+                return valid_event.clone();
+            }
+            GameEvent::Respawn { player_id, position } => {
+                self.respawns.remove(player_id);
+                self.move_budgets.remove(player_id);
+                if let Some(player) = self.players.get_mut(player_id) {
+                    player.lives = self.max_lives;
+                    player.position = position.clone();
+                }
+                // don't let shots rewind to before the respawn
+                let mut history = PositionHistory::default();
+                history.record(now_millis(), position.clone());
+                self.histories.insert(*player_id, history);
+                return valid_event.clone();
+            }
+            GameEvent::Ready { ready } => {
+                let id = self.get_player_id(client_id);
+                if *ready {
+                    self.ready.insert(id);
+                } else {
+                    self.ready.remove(&id);
+                }
+                return self.lobby_event();
+            }
+            GameEvent::ForceStart => {
+                self.forced_start = true;
+                return self.lobby_event();
+            }
+            GameEvent::MazeRequest => {
+                return GameEvent::MazeData {
+                    maze: self.maze.clone(),
+                    checksum: self.maze.checksum(),
+                };
             }
             _ => {}
         }
@@ -178,10 +425,88 @@ impl GameState {
         eve
     }
 
+    pub fn is_alive(&self, id: u8) -> bool {
+        self.players.get(&id).is_some_and(|player| player.lives > 0)
+    }
+
+    /// The players who just ran out of lives and haven't been announced dead yet
+    pub fn dead_players(&self) -> Vec<u8> {
+        self.players
+            .values()
+            .filter(|player| player.lives == 0 && !self.respawns.contains_key(&player.id))
+            .map(|player| player.id)
+            .collect()
+    }
+
+    /// A valid name for a joining player, made unique among the players of the game
+    pub fn player_name(&self, requested: &str) -> Result<String, NameError> {
+        let name = validate_name(requested)?;
+        Ok(unique_name(&name, self.players.values().map(|player| player.name.as_str())))
+    }
+
+    /// Builds the death event of a player according to the game mode
+    pub fn death_of(&self, player_id: u8) -> GameEvent {
+        let respawn_in = match self.mode {
+            GameMode::LastManStanding => None,
+            GameMode::Deathmatch { .. } => Some(RESPAWN_DELAY.as_secs() as u8),
+        };
+        GameEvent::Death { player_id, respawn_in }
+    }
+
+    /// The dead players whose respawn delay is over
+    pub fn due_respawns(&self) -> Vec<u8> {
+        let now = Instant::now();
+        self.respawns
+            .iter()
+            .filter(|(_, at)| **at <= now)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
     pub fn determine_winner(&self) -> Option<u8> {
         if self.players.len() == 1 && self.stage == Stage::InGame {
-            for (id, _) in &self.players {
-                return Some(*id);
+            return self.players.keys().next().copied();
+        }
+        None
+    }
+
+    /// The player with the most kills, None on a tie
+    pub fn top_fragger(&self) -> Option<u8> {
+        let best = self.players
+            .values()
+            .map(|player| player.score.kills)
+            .max()?;
+        let mut leaders = self.players.values().filter(|player| player.score.kills == best);
+        match (leaders.next(), leaders.next()) {
+            (Some(leader), None) => Some(leader.id),
+            _ => None,
+        }
+    }
+
+    /// The score of every player, as broadcast to the clients
+    pub fn scores(&self) -> HashMap<u8, Score> {
+        self.players
+            .iter()
+            .map(|(id, player)| (*id, player.score))
+            .collect()
+    }
+
+    /// Why the game should end now, if it should
+    pub fn end_reason(&self) -> Option<EndGameReason> {
+        if self.stage != Stage::InGame {
+            return None;
+        }
+        if let Some(winner) = self.determine_winner() {
+            return Some(EndGameReason::PlayerWon { winner });
+        }
+        if let GameMode::Deathmatch { frag_limit, time_limit } = self.mode {
+            let leader = self.players.values().find(|player| player.score.kills >= frag_limit);
+            if let Some(player) = leader {
+                return Some(EndGameReason::FragLimit { winner: player.id });
+            }
+            let elapsed = self.started_at.map(|at| at.elapsed()).unwrap_or_default();
+            if elapsed >= Duration::from_secs(time_limit) {
+                return Some(EndGameReason::TimeLimit { winner: self.top_fragger() });
             }
         }
         None
@@ -193,20 +518,120 @@ impl GameState {
         id
     }
     pub fn set_lvl(&mut self, lvl: usize) {
+        self.set_maze(lvl, Maze::level(lvl));
+    }
+
+    /// Plays on the given maze, `lvl` only identifies it
+    pub fn set_maze(&mut self, lvl: usize, maze: Maze) {
         self.lvl = lvl;
-        self.spawn_positions = get_spawn_positions();
+        self.maze = maze;
+        self.spawn_positions = get_spawn_positions(&self.maze);
     }
 
-    pub fn random_spawn(&mut self) -> Position {
+    /// Picks the spawn point farthest (by path) from the living players.
+    /// Spawn points are never used up, ties are broken randomly.
+    pub fn random_spawn(&self) -> Position {
         let mut rng = thread_rng();
-        let gen = rng.gen_range(0..self.spawn_positions.len());
-        self.spawn_positions.remove(gen)
+        let players: Vec<Vec<Vec<Option<usize>>>> = self.players
+            .values()
+            .filter(|player| player.lives > 0)
+            .filter_map(|player| self.maze.cell_at(player.position.x, player.position.z))
+            .map(|cell| distances_from(&self.maze, cell))
+            .collect();
+        let scores: Vec<usize> = self.spawn_positions
+            .iter()
+            .map(|spawn| {
+                let (row, col) = match self.maze.cell_at(spawn.x, spawn.z) {
+                    Some(cell) => cell,
+                    None => {
+                        return 0;
+                    }
+                };
+                players
+                    .iter()
+                    .map(|dist| dist[row][col].unwrap_or(usize::MAX))
+                    .min()
+                    .unwrap_or(usize::MAX)
+            })
+            .collect();
+        let best = match scores.iter().max() {
+            Some(best) => *best,
+            None => {
+                return self.maze.cell_center(0, 0);
+            }
+        };
+        let candidates: Vec<&Position> = self.spawn_positions
+            .iter()
+            .zip(scores.iter())
+            .filter(|(_, score)| **score == best)
+            .map(|(spawn, _)| spawn)
+            .collect();
+        candidates[rng.gen_range(0..candidates.len())].clone()
+    }
+
+    /// Checks a move sent by a client against the maze walls and the players' speed,
+    /// returns where the player is allowed to be
+    pub fn correct_move(&mut self, client_id: u64, at: &Position) -> Position {
+        let id = self.get_player_id(client_id);
+        let from = match self.players.get(&id) {
+            Some(player) => player.position.clone(),
+            None => {
+                return at.clone();
+            }
+        };
+        let budget = self.move_budgets.entry(id).or_default();
+        let max_distance = budget.refill();
+        let allowed = correct_move(&self.maze, &from, at, max_distance);
+        budget.spend(((allowed.x - from.x).powi(2) + (allowed.z - from.z).powi(2)).sqrt());
+        allowed
+    }
+
+    /// Replays a shot as the shooter saw it: players are moved back to where they were at
+    /// `timestamp` (at most `MAX_REWIND_MS` ago) and the closest one in front of the
+    /// first wall is hit
+    pub fn resolve_fire(
+        &self,
+        shooter: u8,
+        origin: &Position,
+        direction: &Position,
+        timestamp: u64
+    ) -> Option<u8> {
+        let now = now_millis();
+        let time = timestamp.clamp(now.saturating_sub(MAX_REWIND_MS), now);
+        let position_at = |id: u8| {
+            self.histories
+                .get(&id)
+                .and_then(|history| history.at(time))
+                .or_else(|| self.players.get(&id).map(|player| player.position.clone()))
+        };
+
+        // the shot leaves from where the server thinks the shooter was
+        let mut start = position_at(shooter)?;
+        start.y = origin.y.clamp(start.y, start.y + PLAYER_HEIGHT);
+        let length = (direction.x.powi(2) + direction.y.powi(2) + direction.z.powi(2)).sqrt();
+        if length <= f32::EPSILON {
+            return None;
+        }
+        let direction = Position::new(direction.x / length, direction.y / length, direction.z / length);
+
+        let range = wall_distance(&self.maze, &start, &direction, WEAPON_RANGE);
+        self.players
+            .keys()
+            .filter(|id| **id != shooter && self.is_alive(**id))
+            .filter_map(|id| {
+                let target = position_at(*id)?;
+                ray_hits_player(&start, &direction, &target).map(|distance| (*id, distance))
+            })
+            .filter(|(_, distance)| *distance <= range)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(id, _)| id)
     }
+
     pub fn get_player_id(&self, client_id: u64) -> u8 {
         let mut id: u8 = u8::MAX;
         for (k, v) in &self.players {
             if v.client_id.eq(&client_id) {
-                id = k.clone();
+                id = *k;
                 break;
             }
         }
@@ -224,3 +649,194 @@ impl GameState {
         client_id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawns_are_reused_and_avoid_players() {
+        let mut state = GameState::default();
+        state.set_lvl(3);
+        for id in 0..20u8 {
+            let position = state.random_spawn();
+            let spawn_cell = state.maze.cell_at(position.x, position.z).unwrap();
+            for player in state.players.values() {
+                let cell = state.maze.cell_at(player.position.x, player.position.z).unwrap();
+                assert!(id as usize >= SPAWN_POINTS || cell != spawn_cell, "spawned on a player");
+            }
+            let event = GameEvent::PlayerJoined {
+                player_id: id,
+                name: format!("p{}", id),
+                position,
+                client_id: id as u64,
+            };
+            state.consume(&event, id as u64);
+        }
+        assert_eq!(state.players.len(), 20);
+    }
+
+    /// Two players in the same corridor of level 1, player 0 facing player 1
+    fn duel(mode: GameMode) -> (GameState, GameEvent) {
+        let mut state = GameState::default();
+        state.set_lvl(1);
+        state.mode = mode;
+        for (id, cell) in [(0u8, (1, 0)), (1u8, (2, 0))] {
+            let event = GameEvent::PlayerJoined {
+                player_id: id,
+                name: format!("p{}", id),
+                position: state.maze.cell_center(cell.0, cell.1),
+                client_id: id as u64,
+            };
+            state.consume(&event, id as u64);
+        }
+        state.consume(&GameEvent::BeginGame { player_list: HashMap::new() }, u64::MAX);
+
+        let mut origin = state.maze.cell_center(1, 0);
+        origin.y += 0.6;
+        let fire = GameEvent::Fire {
+            origin,
+            direction: Position::new(0.0, 0.0, 1.0),
+            timestamp: now_millis(),
+        };
+        (state, fire)
+    }
+
+    #[test]
+    fn shots_cost_lives_until_death() {
+        let (mut state, fire) = duel(GameMode::LastManStanding);
+        assert!(!state.validate(&GameEvent::Impact { id: 1, lives: 0, attacker: 0 }, 0));
+        assert!(!state.validate(&state.death_of(1), 1));
+        for lives in (0..NBR_OF_LIVES).rev() {
+            assert!(state.validate(&fire, 0));
+            assert_eq!(state.consume(&fire, 0), GameEvent::Impact { id: 1, lives, attacker: 0 });
+        }
+        assert_eq!(state.dead_players(), vec![1]);
+        let shooter = state.players[&0].score;
+        assert_eq!((shooter.kills, shooter.damage), (1, NBR_OF_LIVES as u32));
+        state.consume(&state.death_of(1), u64::MAX);
+        assert_eq!(state.end_reason(), Some(EndGameReason::PlayerWon { winner: 0 }));
+    }
+
+    #[test]
+    fn deathmatch_players_respawn_until_the_frag_limit() {
+        let mode = GameMode::Deathmatch { frag_limit: 2, time_limit: 60 };
+        let (mut state, fire) = duel(mode);
+        for kills in 1..=2 {
+            for _ in 0..NBR_OF_LIVES {
+                state.consume(&fire, 0);
+            }
+            assert_eq!(state.players[&0].score.kills, kills);
+            state.consume(&state.death_of(1), u64::MAX);
+            assert!(state.dead_players().is_empty());
+            assert!(!state.is_alive(1));
+            assert!(!state.validate(&fire, 1));
+            assert_eq!(state.players[&1].score.deaths, kills);
+            if kills == 1 {
+                assert_eq!(state.end_reason(), None);
+                state.respawns.insert(1, Instant::now());
+                assert_eq!(state.due_respawns(), vec![1]);
+                let position = state.maze.cell_center(2, 0);
+                state.consume(&GameEvent::Respawn { player_id: 1, position }, u64::MAX);
+                assert_eq!(state.players[&1].lives, NBR_OF_LIVES);
+            }
+        }
+        assert_eq!(state.end_reason(), Some(EndGameReason::FragLimit { winner: 0 }));
+    }
+
+    #[test]
+    fn shots_rewind_by_the_latency_not_the_client_clock() {
+        let (mut state, fire) = duel(GameMode::LastManStanding);
+        // player 1 crossed the line of fire, in front of the shooter 100ms ago
+        let now = now_millis();
+        let before = state.maze.cell_center(2, 0);
+        let (mut left, mut aside) = (before.clone(), before.clone());
+        left.x -= 0.7;
+        aside.x += 0.7;
+        let mut history = PositionHistory::default();
+        history.record(now - MAX_REWIND_MS, left);
+        history.record(now - 100, before);
+        history.record(now, aside.clone());
+        state.histories.insert(1, history);
+        state.players.get_mut(&1).unwrap().position = aside;
+
+        for skew in [3_600_000i64, -3_600_000] {
+            let mut fire = fire.clone();
+            if let GameEvent::Fire { timestamp, .. } = &mut fire {
+                *timestamp = now.saturating_add_signed(skew);
+            }
+            // trusting the client's clock misses or rewinds too far
+            assert_eq!(state.consume(&fire, 0), fire);
+            stamp_shot(&mut fire, Duration::from_millis(200));
+            assert!(matches!(state.consume(&fire, 0), GameEvent::Impact { id: 1, .. }));
+        }
+    }
+
+    #[test]
+    fn clients_cannot_start_end_or_leave_for_others() {
+        let mut state = GameState::default();
+        for id in 0..2u8 {
+            let event = GameEvent::PlayerJoined {
+                player_id: id,
+                name: format!("p{}", id),
+                position: state.random_spawn(),
+                client_id: id as u64,
+            };
+            state.consume(&event, id as u64);
+        }
+        assert_eq!(state.stage, Stage::PreGame);
+        let begin = GameEvent::BeginGame { player_list: state.players.clone() };
+        assert!(!state.validate(&begin, 0));
+        assert!(!state.validate(&GameEvent::PlayerDisconnected { player_id: 1 }, 0));
+        let joined = GameEvent::PlayerJoined {
+            player_id: 7,
+            name: "ghost".to_string(),
+            position: Position::default(),
+            client_id: 0,
+        };
+        assert!(!state.validate(&joined, 0));
+
+        state.consume(&begin, u64::MAX);
+        let end = GameEvent::EndGame { reason: EndGameReason::PlayerWon { winner: 0 } };
+        assert!(!state.validate(&end, 0));
+    }
+
+    #[test]
+    fn clients_cannot_send_server_events() {
+        let (state, _) = duel(GameMode::LastManStanding);
+        let maze = state.maze.clone();
+        let room = RoomInfo {
+            id: 0,
+            name: "Main".to_string(),
+            lvl: 1,
+            level: maze.name.clone(),
+            mode: GameMode::default(),
+            stage: Stage::InGame,
+            players: 2,
+            max_players: 10,
+            lives: NBR_OF_LIVES,
+        };
+        let events = [
+            GameEvent::AccessForbidden,
+            GameEvent::NameRejected { reason: String::new() },
+            GameEvent::PositionCorrection { position: Position::default() },
+            GameEvent::MazeData { maze: maze.clone(), checksum: maze.checksum() },
+            GameEvent::Scores { scores: state.scores() },
+            GameEvent::NextRound { lvl: 2, level: String::new(), in_secs: 10 },
+            GameEvent::Kicked { reason: String::new() },
+            GameEvent::ServerMessage { text: String::new() },
+            GameEvent::RoomList { rooms: vec![room.clone()] },
+            GameEvent::RoomJoined { room: room.clone() },
+            GameEvent::RoomRefused { reason: String::new() },
+            GameEvent::Spectate { lvl: 1, checksum: maze.checksum(), player_list: HashMap::new() },
+            GameEvent::Lobby { players: HashMap::new(), host: None, min_players: 2, countdown: None },
+            GameEvent::Timer { duration: 3 },
+            GameEvent::ListRooms,
+            GameEvent::JoinRoom { room_id: 0 },
+            GameEvent::CreateRoom { name: "room".to_string(), lvl: None, mode: None },
+        ];
+        for event in events {
+            assert!(!state.validate(&event, 0), "accepted {:?}", event);
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use crate::*;
+use rand::{ rngs::StdRng, seq::SliceRandom, Rng, SeedableRng };
+use serde::{ Deserialize, Serialize };
+
+/// The algorithm used to carve the passages of a generated maze
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Algorithm {
+    /// Long winding corridors with few dead ends
+    Backtracker,
+    /// Short branches, lots of dead ends
+    Prim,
+}
+
+/// Knobs for the maze generator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MazeParams {
+    pub rows: usize,
+    pub cols: usize,
+    pub seed: u64,
+    pub algorithm: Algorithm,
+    /// Fraction (0.0 - 1.0) of the carved dead ends that are kept, the others are opened up
+    pub dead_end_ratio: f32,
+    /// Number of extra walls removed after carving, each one adds a loop
+    pub loops: usize,
+}
+
+impl MazeParams {
+    /// Parameters matching the difficulty of a level, level 3 being the hardest
+    pub fn for_level(lvl: usize, seed: u64) -> Self {
+        let (algorithm, dead_end_ratio, loops) = match lvl {
+            1 => (Algorithm::Backtracker, 0.2, 12),
+            2 => (Algorithm::Backtracker, 0.6, 6),
+            _ => (Algorithm::Prim, 1.0, 0),
+        };
+        Self {
+            rows: 10,
+            cols: 10,
+            seed,
+            algorithm,
+            dead_end_ratio,
+            loops,
+        }
+    }
+}
+
+/// Walls of every cell while the maze is being carved
+struct Cells {
+    rows: usize,
+    cols: usize,
+    /// wall on the right side of a cell
+    right: Vec<Vec<bool>>,
+    /// wall on the bottom side of a cell
+    bottom: Vec<Vec<bool>>,
+}
+
+impl Cells {
+    fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            right: vec![vec![true; cols]; rows],
+            bottom: vec![vec![true; cols]; rows],
+        }
+    }
+
+    fn neighbours(&self, (r, c): (usize, usize)) -> Vec<(usize, usize)> {
+        let mut list = Vec::new();
+        if r > 0 {
+            list.push((r - 1, c));
+        }
+        if c > 0 {
+            list.push((r, c - 1));
+        }
+        if r + 1 < self.rows {
+            list.push((r + 1, c));
+        }
+        if c + 1 < self.cols {
+            list.push((r, c + 1));
+        }
+        list
+    }
+
+    fn has_wall(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        let ((r1, c1), (r2, _)) = if a <= b { (a, b) } else { (b, a) };
+        if r1 == r2 {
+            self.right[r1][c1]
+        } else {
+            self.bottom[r1][c1]
+        }
+    }
+
+    fn open(&mut self, a: (usize, usize), b: (usize, usize)) {
+        let ((r1, c1), (r2, _)) = if a <= b { (a, b) } else { (b, a) };
+        if r1 == r2 {
+            self.right[r1][c1] = false;
+        } else {
+            self.bottom[r1][c1] = false;
+        }
+    }
+
+    fn open_sides(&self, cell: (usize, usize)) -> usize {
+        self.neighbours(cell)
+            .into_iter()
+            .filter(|&n| !self.has_wall(cell, n))
+            .count()
+    }
+
+    fn dead_ends(&self) -> Vec<(usize, usize)> {
+        let mut list = Vec::new();
+        for r in 0..self.rows {
+            for c in 0..self.cols {
+                if self.open_sides((r, c)) == 1 {
+                    list.push((r, c));
+                }
+            }
+        }
+        list
+    }
+
+    fn backtracker(&mut self, rng: &mut StdRng) {
+        let mut visited = vec![vec![false; self.cols]; self.rows];
+        let mut stack = vec![(0, 0)];
+        visited[0][0] = true;
+        while let Some(&cell) = stack.last() {
+            let next: Vec<_> = self
+                .neighbours(cell)
+                .into_iter()
+                .filter(|&(r, c)| !visited[r][c])
+                .collect();
+            match next.choose(rng) {
+                Some(&(r, c)) => {
+                    self.open(cell, (r, c));
+                    visited[r][c] = true;
+                    stack.push((r, c));
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    fn prim(&mut self, rng: &mut StdRng) {
+        let mut visited = vec![vec![false; self.cols]; self.rows];
+        let mut frontier = Vec::new();
+        visited[0][0] = true;
+        for n in self.neighbours((0, 0)) {
+            frontier.push(((0, 0), n));
+        }
+        while !frontier.is_empty() {
+            let (from, (r, c)) = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+            if visited[r][c] {
+                continue;
+            }
+            visited[r][c] = true;
+            self.open(from, (r, c));
+            for n in self.neighbours((r, c)) {
+                if !visited[n.0][n.1] {
+                    frontier.push(((r, c), n));
+                }
+            }
+        }
+    }
+
+    /// Opens dead ends until only `ratio` of them remain
+    fn braid(&mut self, ratio: f32, rng: &mut StdRng) {
+        let mut dead_ends = self.dead_ends();
+        let keep = ((dead_ends.len() as f32) * ratio.clamp(0.0, 1.0)).round() as usize;
+        dead_ends.shuffle(rng);
+        for cell in dead_ends {
+            if self.dead_ends().len() <= keep {
+                break;
+            }
+            // it may already have been opened by a previous one
+            if self.open_sides(cell) != 1 {
+                continue;
+            }
+            let closed: Vec<_> = self
+                .neighbours(cell)
+                .into_iter()
+                .filter(|&n| self.has_wall(cell, n))
+                .collect();
+            if let Some(&n) = closed.choose(rng) {
+                self.open(cell, n);
+            }
+        }
+    }
+
+    /// Removes up to `count` random inner walls
+    fn add_loops(&mut self, count: usize, rng: &mut StdRng) {
+        let mut walls = Vec::new();
+        for r in 0..self.rows {
+            for c in 0..self.cols {
+                if c + 1 < self.cols && self.right[r][c] {
+                    walls.push(((r, c), (r, c + 1)));
+                }
+                if r + 1 < self.rows && self.bottom[r][c] {
+                    walls.push(((r, c), (r + 1, c)));
+                }
+            }
+        }
+        walls.shuffle(rng);
+        for (a, b) in walls.into_iter().take(count) {
+            self.open(a, b);
+        }
+    }
+
+    /// Converts to the per-cell walls used by `Maze`
+    fn to_walls(&self) -> Vec<Vec<CellWalls>> {
+        let mut walls = vec![vec![CellWalls::NONE; self.cols]; self.rows];
+        for (r, line) in walls.iter_mut().enumerate() {
+            for (c, cell) in line.iter_mut().enumerate() {
+                cell.set(CellWalls::TOP, r == 0 || self.bottom[r - 1][c]);
+                cell.set(CellWalls::LEFT, c == 0 || self.right[r][c - 1]);
+                cell.set(CellWalls::BOTTOM, self.bottom[r][c]);
+                cell.set(CellWalls::RIGHT, self.right[r][c]);
+            }
+        }
+        walls
+    }
+}
+
+/// Generates the walls of a maze, the same params always give the same maze
+pub fn generate_maze(params: &MazeParams) -> Vec<Vec<CellWalls>> {
+    let rows = params.rows.max(1);
+    let cols = params.cols.max(1);
+    let mut rng = StdRng::seed_from_u64(params.seed);
+    let mut cells = Cells::new(rows, cols);
+    match params.algorithm {
+        Algorithm::Backtracker => cells.backtracker(&mut rng),
+        Algorithm::Prim => cells.prim(&mut rng),
+    }
+    cells.braid(params.dead_end_ratio, &mut rng);
+    cells.add_loops(params.loops, &mut rng);
+    cells.to_walls()
+}
+
+impl Maze {
+    /// Builds a procedurally generated maze
+    pub fn generate(params: &MazeParams) -> Self {
+        Self::new(format!("Generated-{}", params.seed), generate_maze(params))
+    }
+
+    /// A generated maze as hard as the built-in level `lvl`
+    pub fn generated_level(lvl: usize, seed: u64) -> Self {
+        let mut maze = Self::generate(&MazeParams::for_level(lvl, seed));
+        maze.name = format!("Generated{}-{}", lvl, seed);
+        maze
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(algorithm: Algorithm, dead_end_ratio: f32, loops: usize, seed: u64) -> MazeParams {
+        MazeParams { rows: 8, cols: 12, seed, algorithm, dead_end_ratio, loops }
+    }
+
+    /// Openings beyond the ones a maze without any loop would have
+    fn loop_count(maze: &Maze) -> usize {
+        let mut openings = 0;
+        for row in 0..maze.rows() {
+            for col in 0..maze.cols() {
+                openings += maze
+                    .open_neighbours((row, col))
+                    .into_iter()
+                    .filter(|&other| other > (row, col))
+                    .count();
+            }
+        }
+        openings + 1 - maze.rows() * maze.cols()
+    }
+
+    #[test]
+    fn same_seed_same_maze() {
+        for algorithm in [Algorithm::Backtracker, Algorithm::Prim] {
+            let maze = Maze::generate(&params(algorithm, 0.5, 4, 7));
+            assert_eq!(maze, Maze::generate(&params(algorithm, 0.5, 4, 7)));
+            assert_ne!(maze.cells, Maze::generate(&params(algorithm, 0.5, 4, 8)).cells);
+            assert_eq!((maze.rows(), maze.cols()), (8, 12));
+        }
+    }
+
+    #[test]
+    fn every_cell_is_reachable() {
+        for algorithm in [Algorithm::Backtracker, Algorithm::Prim] {
+            for seed in 0..5 {
+                let maze = Maze::generate(&params(algorithm, 1.0, 0, seed));
+                let cells: Vec<Position> = (0..maze.rows())
+                    .flat_map(|row| (0..maze.cols()).map(move |col| (row, col)))
+                    .map(|(row, col)| maze.cell_center(row, col))
+                    .collect();
+                assert!(all_reachable(&maze, &cells), "{:?} seed {}", algorithm, seed);
+                // carving alone gives a perfect maze
+                assert_eq!(loop_count(&maze), 0, "{:?} seed {}", algorithm, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn braiding_removes_dead_ends() {
+        for seed in 0..5 {
+            let dead_ends: Vec<usize> = [1.0, 0.5, 0.0]
+                .iter()
+                .map(|&ratio| Maze::generate(&params(Algorithm::Prim, ratio, 0, seed)))
+                .map(|maze| analyze(&maze).dead_ends)
+                .collect();
+            assert!(dead_ends[0] > dead_ends[1], "seed {}: {:?}", seed, dead_ends);
+            assert!(dead_ends[1] > dead_ends[2], "seed {}: {:?}", seed, dead_ends);
+            assert_eq!(dead_ends[2], 0, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn harder_levels_have_more_dead_ends() {
+        for seed in 0..10 {
+            let easy = analyze(&Maze::generated_level(1, seed));
+            let hard = analyze(&Maze::generated_level(3, seed));
+            assert!(easy.dead_ends < hard.dead_ends, "seed {}", seed);
+            assert!(loop_count(&Maze::generated_level(1, seed)) > 0, "seed {}", seed);
+        }
+        let maze = Maze::generated_level(2, 42);
+        assert_eq!(maze.name, "Generated2-42");
+        assert_ne!(maze.checksum(), Maze::generated_level(3, 42).checksum());
+    }
+
+    #[test]
+    fn each_removed_wall_adds_a_loop() {
+        for seed in 0..5 {
+            for loops in [0, 1, 5, 10] {
+                let maze = Maze::generate(&params(Algorithm::Backtracker, 1.0, loops, seed));
+                assert_eq!(loop_count(&maze), loops, "seed {}", seed);
+                assert_eq!(analyze(&maze).components, 1);
+            }
+        }
+    }
+}
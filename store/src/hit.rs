@@ -0,0 +1,165 @@
+use crate::*;
+use serde::{ Deserialize, Serialize };
+use std::{ collections::VecDeque, time::{ Duration, SystemTime } };
+
+/// How far back in time a shot may be rewound
+pub const MAX_REWIND_MS: u64 = 300;
+/// How long positions are remembered for rewinding
+const HISTORY_MS: u64 = 1000;
+/// Radius of the vertical cylinder used as a player hitbox
+pub const PLAYER_HIT_RADIUS: f32 = 0.3;
+/// Height of a player hitbox, measured from the ground
+pub const PLAYER_HEIGHT: f32 = 1.2;
+/// Shots don't go further than this
+pub const WEAPON_RANGE: f32 = 50.0;
+
+/// What a player was killed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum WeaponKind {
+    #[default]
+    Blaster,
+}
+
+impl std::fmt::Display for WeaponKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WeaponKind::Blaster => write!(f, "blaster"),
+        }
+    }
+}
+
+/// Milliseconds since the UNIX epoch, used to timestamp shots and positions
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|time| time.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Replaces the time of a shot, read from the shooter's clock, with the time the server
+/// thinks it was fired: half a round trip ago, rewound at most `MAX_REWIND_MS`
+pub fn stamp_shot(event: &mut GameEvent, rtt: Duration) {
+    if let GameEvent::Fire { timestamp, .. } = event {
+        let latency = (rtt.as_millis() as u64) / 2;
+        *timestamp = now_millis().saturating_sub(latency.min(MAX_REWIND_MS));
+    }
+}
+
+/// The recent positions of a player, oldest first
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PositionHistory {
+    entries: VecDeque<(u64, Position)>,
+}
+
+impl PositionHistory {
+    pub fn record(&mut self, time: u64, position: Position) {
+        self.entries.push_back((time, position));
+        while let Some((oldest, _)) = self.entries.front() {
+            if *oldest + HISTORY_MS >= time {
+                break;
+            }
+            self.entries.pop_front();
+        }
+    }
+
+    /// Where the player was at the given time, interpolated between two records
+    pub fn at(&self, time: u64) -> Option<Position> {
+        let after = self.entries.iter().position(|(t, _)| *t >= time);
+        match after {
+            None => self.entries.back().map(|(_, position)| position.clone()),
+            Some(0) => self.entries.front().map(|(_, position)| position.clone()),
+            Some(index) => {
+                let (t0, p0) = &self.entries[index - 1];
+                let (t1, p1) = &self.entries[index];
+                let ratio = if t1 == t0 { 1.0 } else { ((time - t0) as f32) / ((t1 - t0) as f32) };
+                Some(
+                    Position::new(
+                        p0.x + (p1.x - p0.x) * ratio,
+                        p0.y + (p1.y - p0.y) * ratio,
+                        p0.z + (p1.z - p0.z) * ratio
+                    )
+                )
+            }
+        }
+    }
+}
+
+/// Distance along a ray (with a normalized direction) to a player hitbox, if it is hit
+pub fn ray_hits_player(origin: &Position, direction: &Position, target: &Position) -> Option<f32> {
+    // intersection with the infinite vertical cylinder, in the xz plane
+    let (ox, oz) = (origin.x - target.x, origin.z - target.z);
+    let a = direction.x * direction.x + direction.z * direction.z;
+    if a <= f32::EPSILON {
+        return None;
+    }
+    let b = 2.0 * (ox * direction.x + oz * direction.z);
+    let c = ox * ox + oz * oz - PLAYER_HIT_RADIUS * PLAYER_HIT_RADIUS;
+    let delta = b * b - 4.0 * a * c;
+    if delta < 0.0 {
+        return None;
+    }
+    let near = (-b - delta.sqrt()) / (2.0 * a);
+    let far = (-b + delta.sqrt()) / (2.0 * a);
+    let distance = if near >= 0.0 { near } else { far };
+    if distance < 0.0 {
+        return None;
+    }
+    let height = origin.y + direction.y * distance - (target.y - 0.2);
+    if !(0.0..=PLAYER_HEIGHT).contains(&height) {
+        return None;
+    }
+    Some(distance)
+}
+
+/// Distance along a ray (with a normalized direction) to the first wall, at most `max`
+pub fn wall_distance(maze: &Maze, origin: &Position, direction: &Position, max: f32) -> f32 {
+    let target = Position::new(
+        origin.x + direction.x * max,
+        origin.y,
+        origin.z + direction.z * max
+    );
+    let flat = (direction.x * direction.x + direction.z * direction.z).sqrt();
+    if flat <= f32::EPSILON {
+        return max;
+    }
+    // walk the shot like a move without any speed limit
+    let stop = correct_move(maze, origin, &target, max * flat);
+    let travelled = ((stop.x - origin.x).powi(2) + (stop.z - origin.z).powi(2)).sqrt();
+    if stop == target {
+        max
+    } else {
+        travelled / flat
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_interpolates() {
+        let mut history = PositionHistory::default();
+        history.record(1000, Position::new(0.0, 0.2, 0.0));
+        history.record(1100, Position::new(1.0, 0.2, 0.0));
+        assert_eq!(history.at(1050).unwrap().x, 0.5);
+        assert_eq!(history.at(900).unwrap().x, 0.0);
+        assert_eq!(history.at(2000).unwrap().x, 1.0);
+    }
+
+    #[test]
+    fn walls_stop_shots() {
+        let maze = Maze::level(1);
+        // (1, 0) and (1, 1) are separated by a wall, (1, 0) and (2, 0) are not
+        let shooter = maze.cell_center(1, 0);
+        let through_wall = maze.cell_center(1, 1);
+        let open = maze.cell_center(2, 0);
+        let east = Position::new(1.0, 0.0, 0.0);
+        let south = Position::new(0.0, 0.0, 1.0);
+
+        let hit = ray_hits_player(&shooter, &east, &through_wall).unwrap();
+        assert!(wall_distance(&maze, &shooter, &east, WEAPON_RANGE) < hit);
+
+        let hit = ray_hits_player(&shooter, &south, &open).unwrap();
+        assert!(wall_distance(&maze, &shooter, &south, WEAPON_RANGE) > hit);
+    }
+}
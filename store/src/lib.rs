@@ -1,11 +1,32 @@
+mod analysis;
+mod cell;
 mod constant;
 mod event;
 mod game_state;
+mod generator;
+mod hit;
+mod lobby;
 mod lvl;
+mod maze;
+mod maze_file;
+mod movement;
 mod player;
+mod room;
+mod rotation;
+mod spectator;
 
+pub use analysis::*;
+pub use cell::*;
 pub use constant::*;
 pub use event::*;
 pub use game_state::*;
+pub use generator::*;
+pub use hit::*;
+pub use lobby::*;
 pub use lvl::*;
+pub use maze::*;
+pub use maze_file::*;
+pub use movement::*;
 pub use player::*;
+pub use room::*;
+pub use rotation::*;
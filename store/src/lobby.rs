@@ -0,0 +1,173 @@
+use crate::*;
+use serde::{ Deserialize, Serialize };
+use std::collections::HashMap;
+
+/// When the lobby countdown may start
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LobbyConfig {
+    /// No game starts with fewer players than this
+    pub min_players: usize,
+    /// Start once this many players are ready, even if others are not. None waits for everyone
+    pub min_ready: Option<usize>,
+    /// Length of the countdown, in seconds
+    pub countdown: u8,
+}
+
+impl Default for LobbyConfig {
+    fn default() -> Self {
+        Self {
+            min_players: MIN_PLAYERS,
+            min_ready: None,
+            countdown: LOBBY_COUNTDOWN,
+        }
+    }
+}
+
+/// A player waiting in the lobby, as shown to the clients
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LobbyPlayer {
+    pub name: String,
+    pub ready: bool,
+}
+
+/// What happened to the lobby during a one second tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LobbyTick {
+    /// Not enough players are ready
+    Waiting,
+    /// The countdown goes on, with the number of seconds left
+    Countdown(u8),
+    /// The countdown stopped because players left or stopped being ready
+    Cancelled,
+    /// The countdown is over, the game can begin
+    Start,
+}
+
+impl GameState {
+    pub fn is_host(&self, id: u8) -> bool {
+        self.host == Some(id)
+    }
+
+    /// Whether the countdown can run with the players currently in the lobby
+    pub fn can_start(&self) -> bool {
+        let players = self.players.len();
+        let ready = self.players
+            .keys()
+            .filter(|id| self.ready.contains(id))
+            .count();
+        let enough_ready =
+            ready == players ||
+            self.forced_start ||
+            self.lobby.min_ready.is_some_and(|min_ready| ready >= min_ready);
+        players >= self.lobby.min_players && enough_ready
+    }
+
+    /// Moves the lobby countdown forward by one second
+    pub fn tick_lobby(&mut self) -> LobbyTick {
+        if !self.can_start() {
+            self.forced_start = false;
+            return match self.countdown.take() {
+                Some(_) => LobbyTick::Cancelled,
+                None => LobbyTick::Waiting,
+            };
+        }
+        let left = self.countdown.get_or_insert(self.lobby.countdown);
+        if *left == 0 {
+            self.countdown = None;
+            return LobbyTick::Start;
+        }
+        let seconds = *left;
+        *left -= 1;
+        LobbyTick::Countdown(seconds)
+    }
+
+    /// The state of the lobby, sent to everyone whenever it changes
+    pub fn lobby_event(&self) -> GameEvent {
+        let players: HashMap<u8, LobbyPlayer> = self.players
+            .iter()
+            .map(|(id, player)| {
+                (*id, LobbyPlayer {
+                    name: player.name.clone(),
+                    ready: self.ready.contains(id),
+                })
+            })
+            .collect();
+        GameEvent::Lobby {
+            players,
+            host: self.host,
+            min_players: self.lobby.min_players,
+            countdown: self.countdown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lobby(players: u8) -> GameState {
+        let mut state = GameState::default();
+        for id in 0..players {
+            let event = GameEvent::PlayerJoined {
+                player_id: id,
+                name: format!("p{}", id),
+                position: state.random_spawn(),
+                client_id: id as u64,
+            };
+            state.consume(&event, id as u64);
+        }
+        state
+    }
+
+    fn ready(state: &mut GameState, id: u8, ready: bool) {
+        let event = GameEvent::Ready { ready };
+        assert!(state.validate(&event, id as u64));
+        state.consume(&event, id as u64);
+    }
+
+    #[test]
+    fn countdown_waits_for_everyone_and_cancels() {
+        let mut state = lobby(3);
+        ready(&mut state, 0, true);
+        ready(&mut state, 1, true);
+        assert_eq!(state.tick_lobby(), LobbyTick::Waiting);
+        ready(&mut state, 2, true);
+        assert_eq!(state.tick_lobby(), LobbyTick::Countdown(LOBBY_COUNTDOWN));
+        ready(&mut state, 2, false);
+        assert_eq!(state.tick_lobby(), LobbyTick::Cancelled);
+        assert_eq!(state.tick_lobby(), LobbyTick::Waiting);
+
+        ready(&mut state, 2, true);
+        state.consume(&GameEvent::PlayerDisconnected { player_id: 2 }, 2);
+        state.consume(&GameEvent::PlayerDisconnected { player_id: 1 }, 1);
+        assert_eq!(state.tick_lobby(), LobbyTick::Waiting);
+    }
+
+    #[test]
+    fn countdown_ends_with_a_start() {
+        let mut state = lobby(2);
+        state.lobby.countdown = 1;
+        ready(&mut state, 0, true);
+        ready(&mut state, 1, true);
+        assert_eq!(state.tick_lobby(), LobbyTick::Countdown(1));
+        assert_eq!(state.tick_lobby(), LobbyTick::Start);
+    }
+
+    #[test]
+    fn only_the_host_forces_a_start() {
+        let mut state = lobby(3);
+        state.lobby.min_ready = Some(2);
+        assert_eq!(state.host, Some(0));
+        assert!(!state.validate(&GameEvent::ForceStart, 1));
+        assert!(state.validate(&GameEvent::ForceStart, 0));
+        state.consume(&GameEvent::ForceStart, 0);
+        assert!(matches!(state.tick_lobby(), LobbyTick::Countdown(_)));
+
+        // the host leaving hands the lobby over to another player
+        state.consume(&GameEvent::PlayerDisconnected { player_id: 0 }, 0);
+        assert_eq!(state.host, Some(1));
+        ready(&mut state, 1, true);
+        ready(&mut state, 2, true);
+        assert!(state.can_start());
+    }
+}
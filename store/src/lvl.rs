@@ -1,58 +1,107 @@
-use crate::Position;
+use crate::*;
+use std::collections::HashMap;
 
-pub fn get_spawn_positions() -> Vec<Position> {
-    return {
+/// Spawn points of a maze. Unless the map file lists its own, they are spread over the
+/// maze: starting from a corner of the biggest reachable area, each new point is the cell
+/// farthest (by path) from the ones already picked
+pub fn get_spawn_positions(maze: &Maze) -> Vec<Position> {
+    if !maze.spawns.is_empty() {
+        return maze.spawns
+            .iter()
+            .map(|&(row, col)| maze.cell_center(row, col))
+            .collect();
+    }
+    let labels = component_labels(maze);
+    let mut sizes: HashMap<usize, usize> = HashMap::new();
+    for label in labels.iter().flatten() {
+        *sizes.entry(*label).or_insert(0) += 1;
+    }
+    let main = match sizes.iter().max_by_key(|(label, size)| (**size, usize::MAX - **label)) {
+        Some((label, _)) => *label,
+        None => {
+            return Vec::new();
+        }
+    };
+    let cells: Vec<(usize, usize)> = (0..maze.rows())
+        .flat_map(|row| (0..maze.cols()).map(move |col| (row, col)))
+        .filter(|&(row, col)| labels[row][col] == main)
+        .collect();
+
+    let mut picked = vec![cells[0]];
+    // distance from every cell to the closest picked one
+    let mut closest = distances_from(maze, cells[0]);
+    while picked.len() < SPAWN_POINTS.min(cells.len()) {
+        let next = cells
+            .iter()
+            .copied()
+            .max_by_key(|&(row, col)| closest[row][col].unwrap_or(0))
+            .unwrap();
+        if closest[next.0][next.1] == Some(0) {
+            break;
+        }
+        picked.push(next);
+        let dist = distances_from(maze, next);
+        for (row, line) in closest.iter_mut().enumerate() {
+            for (col, d) in line.iter_mut().enumerate() {
+                if let Some(new) = dist[row][col] {
+                    *d = Some(d.map_or(new, |old| old.min(new)));
+                }
+            }
+        }
+    }
+    picked
+        .into_iter()
+        .map(|(row, col)| maze.cell_center(row, col))
+        .collect()
+}
+
+/// Number of built-in levels, numbered from 1
+pub const BUILTIN_LEVELS: usize = 3;
+
+/// The built-in levels in the legacy corner codes (`WALL_DOWN`, `WALL_RIGHT`, ...),
+/// see cell.rs and `cells_from_legacy` for their meaning and the `CellWalls` they become
+pub fn get_mazes(name: &str) -> Vec<Vec<u8>> {
+    if name == "Map1" {
         vec![
-            Position {
-                x: -7.9,
-                y: 0.2,
-                z: -6.0,
-            },
-            Position {
-                x: 3.9,
-                y: 0.2,
-                z: -6.3,
-            },
-            Position {
-                x: 9.5,
-                y: 0.2,
-                z: -6.7,
-            },
-            Position {
-                x: 9.1,
-                y: 0.2,
-                z: 10.5,
-            },
-            Position {
-                x: -6.1,
-                y: 0.2,
-                z: 10.2,
-            },
-            Position {
-                x: -5.2,
-                y: 0.2,
-                z: 2.2,
-            },
-            Position {
-                x: 1.9,
-                y: 0.2,
-                z: -1.4,
-            },
-            Position {
-                x: 0.2,
-                y: 0.2,
-                z: -2.1,
-            },
-            Position {
-                x: -2.1,
-                y: 0.2,
-                z: 6.2,
-            },
-            Position {
-                x: 3.0,
-                y: 0.2,
-                z: 3.8,
-            },
+            vec![4, 3, 2, 3, 3, 3, 3, 4, 3, 3, 1],
+            vec![1, 1, 1, 3, 3, 2, 1, 1, 3, 1, 1],
+            vec![1, 2, 2, 2, 3, 3, 2, 2, 2, 3, 1],
+            vec![1, 3, 3, 2, 3, 3, 1, 2, 3, 2, 1],
+            vec![1, 1, 3, 2, 3, 2, 3, 2, 3, 1, 2],
+            vec![2, 3, 3, 2, 1, 2, 3, 3, 2, 1, 1],
+            vec![1, 3, 3, 2, 1, 2, 2, 1, 2, 1, 1],
+            vec![1, 2, 3, 1, 1, 2, 3, 2, 1, 2, 1],
+            vec![1, 3, 2, 2, 3, 2, 1, 2, 1, 2, 1],
+            vec![1, 3, 3, 3, 2, 4, 2, 2, 2, 3, 1],
+            vec![3, 3, 3, 3, 3, 3, 3, 3, 2, 3, 2],
         ]
-    };
+    } else if name == "Map2" {
+        vec![
+            vec![4, 3, 2, 3, 3, 4, 3, 4, 3, 3, 1],
+            vec![1, 1, 1, 3, 3, 2, 1, 1, 3, 1, 1],
+            vec![1, 2, 2, 3, 3, 3, 2, 1, 2, 3, 1],
+            vec![1, 3, 3, 2, 3, 3, 1, 2, 3, 2, 1],
+            vec![1, 1, 3, 2, 3, 2, 3, 2, 3, 1, 1],
+            vec![1, 3, 3, 2, 1, 2, 3, 3, 2, 1, 1],
+            vec![1, 3, 3, 2, 1, 2, 2, 1, 1, 1, 1],
+            vec![1, 2, 3, 1, 4, 2, 3, 2, 4, 2, 1],
+            vec![1, 3, 2, 2, 3, 2, 1, 2, 4, 2, 1],
+            vec![1, 3, 3, 3, 2, 4, 2, 2, 2, 3, 1],
+            vec![3, 3, 3, 3, 3, 3, 3, 3, 2, 3, 2],
+        ]
+    } else {
+        vec![
+            vec![4, 3, 3, 3, 3, 4, 3, 4, 3, 3, 1],
+            vec![1, 1, 1, 3, 3, 2, 1, 1, 3, 1, 1],
+            vec![1, 2, 4, 3, 4, 3, 2, 1, 3, 3, 1],
+            vec![1, 3, 3, 2, 3, 3, 1, 2, 3, 3, 1],
+            vec![1, 1, 3, 2, 4, 2, 3, 2, 3, 1, 1],
+            vec![1, 3, 3, 2, 4, 3, 3, 3, 2, 1, 1],
+            vec![1, 3, 3, 2, 1, 4, 2, 4, 1, 1, 1],
+            vec![1, 4, 3, 1, 4, 2, 3, 2, 4, 2, 1],
+            vec![1, 3, 2, 4, 3, 2, 1, 4, 4, 2, 1],
+            vec![1, 3, 3, 3, 2, 4, 2, 2, 2, 3, 1],
+            vec![3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 2],
+        ]
+    }
 }
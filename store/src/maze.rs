@@ -0,0 +1,449 @@
+use crate::*;
+use serde::{ Deserialize, Serialize };
+use std::collections::HashMap;
+
+/// Size of a maze cell in world units
+pub const CELL_SIZE: f32 = 2.0;
+/// World coordinate (on both x and z) of the top-left corner of a maze
+pub const MAZE_OFFSET: f32 = -9.0;
+
+/// A playable level shared by the server and the clients
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Maze {
+    pub name: String,
+    /// Walls of every cell, indexed by row then column
+    pub cells: Vec<Vec<CellWalls>>,
+    /// Cells (row, column) chosen as spawn points, computed from the layout when empty
+    #[serde(default)]
+    pub spawns: Vec<(usize, usize)>,
+    pub cell_size: f32,
+    pub offset: (f32, f32),
+}
+
+impl Default for Maze {
+    fn default() -> Self {
+        Self::level(1)
+    }
+}
+
+impl Maze {
+    pub fn new(name: String, cells: Vec<Vec<CellWalls>>) -> Self {
+        Self {
+            name,
+            cells,
+            spawns: Vec::new(),
+            cell_size: CELL_SIZE,
+            offset: (MAZE_OFFSET, MAZE_OFFSET),
+        }
+    }
+
+    /// A maze with only its outer walls
+    pub fn empty(name: String, rows: usize, cols: usize) -> Self {
+        let mut cells = vec![vec![CellWalls::NONE; cols]; rows];
+        for (row, line) in cells.iter_mut().enumerate() {
+            for (col, walls) in line.iter_mut().enumerate() {
+                walls.set(CellWalls::TOP, row == 0);
+                walls.set(CellWalls::BOTTOM, row + 1 == rows);
+                walls.set(CellWalls::LEFT, col == 0);
+                walls.set(CellWalls::RIGHT, col + 1 == cols);
+            }
+        }
+        Self::new(name, cells)
+    }
+
+    /// Builds one of the built-in levels (1, 2 or 3)
+    pub fn level(lvl: usize) -> Self {
+        let name = format!("Map{}", lvl);
+        let grid = get_mazes(&name);
+        Self::from_legacy(name, &grid)
+    }
+
+    /// Builds a maze from the legacy 1-4 corner codes
+    pub fn from_legacy(name: String, grid: &[Vec<u8>]) -> Self {
+        Self::new(name, cells_from_legacy(grid))
+    }
+
+    /// The legacy 1-4 corner codes of this maze
+    pub fn to_legacy(&self) -> Vec<Vec<u8>> {
+        cells_to_legacy(&self.cells)
+    }
+
+    /// Number of cell rows
+    pub fn rows(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Number of cell columns
+    pub fn cols(&self) -> usize {
+        self.cells
+            .first()
+            .map(|row| row.len())
+            .unwrap_or(0)
+    }
+
+    /// Walls around a cell, a cell outside the maze is fully walled
+    pub fn walls(&self, row: usize, col: usize) -> CellWalls {
+        self.cells
+            .get(row)
+            .and_then(|r| r.get(col))
+            .copied()
+            .unwrap_or(CellWalls::ALL)
+    }
+
+    fn side(&self, row: usize, col: usize, side: CellWalls) -> bool {
+        row < self.rows() && col < self.cols() && self.cells[row][col].contains(side)
+    }
+
+    /// Whether a wall goes down (along z) from the given corner
+    pub fn has_down(&self, row: usize, col: usize) -> bool {
+        self.side(row, col, CellWalls::LEFT) ||
+            (col > 0 && self.side(row, col - 1, CellWalls::RIGHT))
+    }
+
+    /// Whether a wall goes right (along x) from the given corner
+    pub fn has_right(&self, row: usize, col: usize) -> bool {
+        self.side(row, col, CellWalls::TOP) ||
+            (row > 0 && self.side(row - 1, col, CellWalls::BOTTOM))
+    }
+
+    /// Every wall of the maze, a wall shared by two cells is only listed once
+    pub fn segments(&self) -> Vec<WallSegment> {
+        let mut segments = Vec::new();
+        for row in 0..=self.rows() {
+            for col in 0..=self.cols() {
+                let start = self.corner_position(row, col);
+                if self.has_down(row, col) {
+                    segments.push(WallSegment {
+                        start,
+                        end: (start.0, start.1 + self.cell_size),
+                    });
+                }
+                if self.has_right(row, col) {
+                    segments.push(WallSegment {
+                        start,
+                        end: (start.0 + self.cell_size, start.1),
+                    });
+                }
+            }
+        }
+        segments
+    }
+
+    /// Adds or removes the wall between two adjacent cells, on both of them
+    pub fn set_wall_between(&mut self, a: (usize, usize), b: (usize, usize), wall: bool) {
+        let ((r1, c1), (r2, c2)) = if a <= b { (a, b) } else { (b, a) };
+        if r2 >= self.rows() || c2 >= self.cols() {
+            return;
+        }
+        let (first, second) = if r1 == r2 && c1 + 1 == c2 {
+            (CellWalls::RIGHT, CellWalls::LEFT)
+        } else if c1 == c2 && r1 + 1 == r2 {
+            (CellWalls::BOTTOM, CellWalls::TOP)
+        } else {
+            return;
+        };
+        self.cells[r1][c1].set(first, wall);
+        self.cells[r2][c2].set(second, wall);
+    }
+
+    /// Adds or removes a wall on one side of a cell, and on the cell behind it if any
+    pub fn toggle_wall(&mut self, row: usize, col: usize, side: CellWalls) {
+        if row >= self.rows() || col >= self.cols() {
+            return;
+        }
+        let wall = !self.cells[row][col].contains(side);
+        let neighbour = match side {
+            CellWalls::TOP if row > 0 => Some((row - 1, col)),
+            CellWalls::LEFT if col > 0 => Some((row, col - 1)),
+            CellWalls::BOTTOM if row + 1 < self.rows() => Some((row + 1, col)),
+            CellWalls::RIGHT if col + 1 < self.cols() => Some((row, col + 1)),
+            _ => None,
+        };
+        match neighbour {
+            Some(other) => self.set_wall_between((row, col), other, wall),
+            None => self.cells[row][col].set(side, wall),
+        }
+    }
+
+    /// Adds or removes a spawn point on a cell
+    pub fn toggle_spawn(&mut self, row: usize, col: usize) {
+        if row >= self.rows() || col >= self.cols() {
+            return;
+        }
+        match self.spawns.iter().position(|&cell| cell == (row, col)) {
+            Some(index) => {
+                self.spawns.remove(index);
+            }
+            None => self.spawns.push((row, col)),
+        }
+    }
+
+    /// World position (x, z) of a wall corner
+    pub fn corner_position(&self, row: usize, col: usize) -> (f32, f32) {
+        (
+            (col as f32) * self.cell_size + self.offset.0,
+            (row as f32) * self.cell_size + self.offset.1,
+        )
+    }
+
+    /// World position of the center of a cell
+    pub fn cell_center(&self, row: usize, col: usize) -> Position {
+        let (x, z) = self.corner_position(row, col);
+        Position::new(x + self.cell_size / 2.0, 0.2, z + self.cell_size / 2.0)
+    }
+
+    /// The cell containing the world position (x, z), if it is inside the maze
+    pub fn cell_at(&self, x: f32, z: f32) -> Option<(usize, usize)> {
+        let col = ((x - self.offset.0) / self.cell_size).floor();
+        let row = ((z - self.offset.1) / self.cell_size).floor();
+        if col < 0.0 || row < 0.0 {
+            return None;
+        }
+        let (row, col) = (row as usize, col as usize);
+        if row >= self.rows() || col >= self.cols() {
+            return None;
+        }
+        Some((row, col))
+    }
+
+    /// Whether a wall separates two cells.
+    /// Cells that are not adjacent are always considered separated.
+    pub fn wall_between(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        let ((r1, c1), (r2, c2)) = if a <= b { (a, b) } else { (b, a) };
+        if r1 == r2 && c1 + 1 == c2 {
+            // wall on the right side of the first cell
+            self.has_down(r1, c2)
+        } else if c1 == c2 && r1 + 1 == r2 {
+            // wall on the bottom side of the first cell
+            self.has_right(r2, c1)
+        } else {
+            true
+        }
+    }
+
+    /// Cells next to the given one that are not separated from it by a wall
+    pub fn open_neighbours(&self, (row, col): (usize, usize)) -> Vec<(usize, usize)> {
+        let mut list = Vec::new();
+        if row > 0 {
+            list.push((row - 1, col));
+        }
+        if col > 0 {
+            list.push((row, col - 1));
+        }
+        if row + 1 < self.rows() {
+            list.push((row + 1, col));
+        }
+        if col + 1 < self.cols() {
+            list.push((row, col + 1));
+        }
+        list.retain(|&other| !self.wall_between((row, col), other));
+        list
+    }
+
+    /// FNV-1a hash of the maze layout, stable across platforms and builds.
+    /// The name is left out so that a renamed copy of a map still matches.
+    pub fn checksum(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut feed = |bytes: &[u8]| {
+            for byte in bytes {
+                hash ^= *byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        feed(&(self.cells.len() as u64).to_le_bytes());
+        for row in &self.cells {
+            feed(&(row.len() as u64).to_le_bytes());
+            for walls in row {
+                feed(&[walls.bits()]);
+            }
+        }
+        feed(&self.cell_size.to_bits().to_le_bytes());
+        feed(&self.offset.0.to_bits().to_le_bytes());
+        feed(&self.offset.1.to_bits().to_le_bytes());
+        hash
+    }
+}
+
+/// The mazes a client already has, by checksum, so that it only downloads the others
+#[derive(Debug, Clone, PartialEq)]
+pub struct MazeCache {
+    mazes: HashMap<u64, Maze>,
+}
+
+impl Default for MazeCache {
+    /// A cache holding the built-in levels
+    fn default() -> Self {
+        let mut cache = Self { mazes: HashMap::new() };
+        for lvl in 1..=BUILTIN_LEVELS {
+            cache.insert(Maze::level(lvl));
+        }
+        cache
+    }
+}
+
+impl MazeCache {
+    pub fn get(&self, checksum: u64) -> Option<&Maze> {
+        self.mazes.get(&checksum)
+    }
+
+    /// Keeps a maze, returns its checksum
+    pub fn insert(&mut self, maze: Maze) -> u64 {
+        let checksum = maze.checksum();
+        self.mazes.insert(checksum, maze);
+        checksum
+    }
+}
+
+/// A wall in world coordinates (x, z), going either along x or along z
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WallSegment {
+    pub start: (f32, f32),
+    pub end: (f32, f32),
+}
+
+impl WallSegment {
+    /// Whether the wall goes along z (vertical on the minimap)
+    pub fn is_vertical(&self) -> bool {
+        self.start.0 == self.end.0
+    }
+
+    pub fn center(&self) -> (f32, f32) {
+        ((self.start.0 + self.end.0) / 2.0, (self.start.1 + self.end.1) / 2.0)
+    }
+
+    pub fn length(&self) -> f32 {
+        (self.end.0 - self.start.0).abs() + (self.end.1 - self.start.1).abs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksum_is_stable_and_follows_the_walls() {
+        let maze = Maze::level(1);
+        assert_eq!(maze.checksum(), Maze::level(1).checksum());
+        assert_ne!(maze.checksum(), Maze::level(2).checksum());
+        let mut renamed = maze.clone();
+        renamed.name = "Copy".to_string();
+        assert_eq!(renamed.checksum(), maze.checksum());
+
+        let mut edited = maze.clone();
+        edited.toggle_wall(5, 5, CellWalls::RIGHT);
+        assert_ne!(edited.checksum(), maze.checksum());
+        edited.toggle_wall(5, 5, CellWalls::RIGHT);
+        assert_eq!(edited.checksum(), maze.checksum());
+        edited.cell_size *= 2.0;
+        assert_ne!(edited.checksum(), maze.checksum());
+    }
+
+    /// A 2 x 2 maze with unit cells whose top-left corner is at the origin
+    fn small() -> Maze {
+        let mut maze = Maze::empty("Small".to_string(), 2, 2);
+        maze.cell_size = 1.0;
+        maze.offset = (0.0, 0.0);
+        maze
+    }
+
+    #[test]
+    fn walls_are_shared_between_neighbours() {
+        let mut maze = small();
+        assert!(!maze.wall_between((0, 0), (0, 1)));
+        maze.set_wall_between((0, 1), (0, 0), true);
+        assert!(maze.cells[0][0].contains(CellWalls::RIGHT));
+        assert!(maze.cells[0][1].contains(CellWalls::LEFT));
+        assert!(maze.wall_between((0, 0), (0, 1)));
+        assert!(maze.wall_between((0, 1), (0, 0)));
+
+        maze.set_wall_between((0, 0), (1, 0), true);
+        assert!(maze.cells[0][0].contains(CellWalls::BOTTOM));
+        assert!(maze.cells[1][0].contains(CellWalls::TOP));
+        maze.set_wall_between((1, 0), (0, 0), false);
+        assert!(!maze.wall_between((0, 0), (1, 0)));
+        assert_eq!(maze.cells[1][0], CellWalls::LEFT | CellWalls::BOTTOM);
+
+        // cells that aren't neighbours are always separated, and never changed
+        let before = maze.clone();
+        assert!(maze.wall_between((0, 0), (1, 1)));
+        maze.set_wall_between((0, 0), (1, 1), false);
+        maze.set_wall_between((1, 1), (1, 2), true);
+        assert_eq!(maze, before);
+        assert_eq!(maze.open_neighbours((1, 0)), vec![(0, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn toggled_walls_stay_symmetric_and_borders_stay_alone() {
+        let mut maze = small();
+        maze.toggle_wall(0, 0, CellWalls::BOTTOM);
+        assert!(maze.cells[1][0].contains(CellWalls::TOP));
+        maze.toggle_wall(1, 0, CellWalls::TOP);
+        assert!(!maze.cells[0][0].contains(CellWalls::BOTTOM));
+        assert_eq!(maze, small());
+
+        // an outer wall has no cell behind it
+        maze.toggle_wall(0, 1, CellWalls::RIGHT);
+        assert_eq!(maze.cells[0][1], CellWalls::TOP);
+        assert!(!maze.has_down(0, 2));
+        assert!(maze.has_down(1, 2));
+
+        let before = maze.clone();
+        maze.toggle_wall(2, 0, CellWalls::TOP);
+        maze.toggle_wall(0, 2, CellWalls::LEFT);
+        assert_eq!(maze, before);
+        assert_eq!(maze.walls(2, 0), CellWalls::ALL);
+    }
+
+    #[test]
+    fn spawns_toggle_inside_the_maze() {
+        let mut maze = small();
+        maze.toggle_spawn(1, 0);
+        maze.toggle_spawn(0, 1);
+        assert_eq!(maze.spawns, vec![(1, 0), (0, 1)]);
+        maze.toggle_spawn(1, 0);
+        assert_eq!(maze.spawns, vec![(0, 1)]);
+        maze.toggle_spawn(2, 0);
+        maze.toggle_spawn(0, 2);
+        assert_eq!(maze.spawns, vec![(0, 1)]);
+    }
+
+    #[test]
+    fn segments_list_each_wall_once() {
+        let mut maze = small();
+        maze.set_wall_between((0, 0), (0, 1), true);
+        let segments = maze.segments();
+        let expected = [
+            ((0.0, 0.0), (0.0, 1.0)),
+            ((0.0, 0.0), (1.0, 0.0)),
+            ((1.0, 0.0), (1.0, 1.0)),
+            ((1.0, 0.0), (2.0, 0.0)),
+            ((2.0, 0.0), (2.0, 1.0)),
+            ((0.0, 1.0), (0.0, 2.0)),
+            ((2.0, 1.0), (2.0, 2.0)),
+            ((0.0, 2.0), (1.0, 2.0)),
+            ((1.0, 2.0), (2.0, 2.0)),
+        ];
+        let expected: Vec<WallSegment> = expected
+            .iter()
+            .map(|&(start, end)| WallSegment { start, end })
+            .collect();
+        assert_eq!(segments, expected);
+        assert!(segments[2].is_vertical());
+        assert_eq!(segments[2].center(), (1.0, 0.5));
+        assert!(segments.iter().all(|segment| segment.length() == 1.0));
+    }
+
+    #[test]
+    fn cache_knows_the_built_in_levels() {
+        let mut cache = MazeCache::default();
+        for lvl in 1..=BUILTIN_LEVELS {
+            let maze = Maze::level(lvl);
+            assert_eq!(cache.get(maze.checksum()), Some(&maze));
+        }
+        let mut custom = Maze::level(1);
+        custom.toggle_wall(0, 0, CellWalls::RIGHT);
+        assert_eq!(cache.get(custom.checksum()), None);
+        let checksum = cache.insert(custom.clone());
+        assert_eq!(cache.get(checksum), Some(&custom));
+    }
+}
@@ -0,0 +1,521 @@
+//! On-disk format for custom levels.
+//!
+//! A maze file is either JSON (a serialized `Maze`, or a `name` and the legacy corner
+//! codes as `grid`, extension `.json`) or ASCII art
+//! (any other extension, `.maze` by convention). The ASCII format alternates two kinds
+//! of lines, one character per corner, wall or cell:
+//!
+//! ```text
+//! # comments start with a '#'
+//! +-+-+-+
+//! |   | |
+//! + +-+ +
+//! |     |
+//! +-+-+-+
+//! ```
+//!
+//! - corner lines (the 1st, 3rd, ...) hold a `+` on even columns and a `-` (wall) or a
+//!   space (opening) on odd columns;
+//! - cell lines (the 2nd, 4th, ...) hold a `|` (wall) or a space (opening) on even columns
+//!   and the cell itself on odd columns: a space, or an `S` to make it a spawn point.
+//!
+//! Trailing spaces may be omitted, the first and last lines must be corner lines.
+
+use crate::*;
+use serde::Deserialize;
+use std::{ fmt, fs, path::{ Path, PathBuf } };
+
+/// Name of the directory holding the custom levels
+pub const MAPS_DIR: &str = "maps";
+/// Environment variable giving the directory of the custom levels
+pub const MAPS_DIR_VAR: &str = "MAZE_MAPS_DIR";
+
+/// Directory scanned for custom levels: `$MAZE_MAPS_DIR`, or else the `maps` directory
+/// next to the executable when there is one, or else `maps` in the working directory
+pub fn maps_dir() -> PathBuf {
+    let exe_dir = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf));
+    resolve_maps_dir(std::env::var_os(MAPS_DIR_VAR).map(PathBuf::from), exe_dir.as_deref())
+}
+
+fn resolve_maps_dir(configured: Option<PathBuf>, exe_dir: Option<&Path>) -> PathBuf {
+    if let Some(dir) = configured {
+        return dir;
+    }
+    match exe_dir.map(|dir| dir.join(MAPS_DIR)) {
+        Some(dir) if dir.is_dir() => dir,
+        _ => PathBuf::from(MAPS_DIR),
+    }
+}
+
+/// Why a maze file could not be loaded. `line` and `column` start at 1, 0 means unknown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MazeFileError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl MazeFileError {
+    fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for MazeFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for MazeFileError {}
+
+/// Parses the ASCII art format described at the top of this module
+pub fn parse_ascii_maze(name: &str, text: &str) -> Result<Maze, MazeFileError> {
+    // keep the original line numbers for the error messages
+    let lines: Vec<(usize, Vec<char>)> = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim_start().starts_with('#') && !line.trim().is_empty())
+        .map(|(i, line)| (i + 1, line.trim_end().chars().collect()))
+        .collect();
+
+    let (first_line, first) = match lines.first() {
+        Some(first) => first,
+        None => {
+            return Err(MazeFileError::new(0, 0, "empty maze file"));
+        }
+    };
+    if lines.len() < 3 || lines.len().is_multiple_of(2) {
+        let (last_line, _) = lines[lines.len() - 1];
+        return Err(
+            MazeFileError::new(last_line + 1, 1, "expected a closing corner line ('+-+...')")
+        );
+    }
+    let width = first.len();
+    if width < 3 || width.is_multiple_of(2) {
+        return Err(
+            MazeFileError::new(*first_line, width + 1, "a corner line must end with a '+'")
+        );
+    }
+
+    let rows = lines.len() / 2;
+    let cols = width / 2;
+    let mut grid = vec![vec![WALL_NONE; cols + 1]; rows + 1];
+    let mut spawns = Vec::new();
+
+    for (index, (line, chars)) in lines.iter().enumerate() {
+        if chars.len() > width {
+            return Err(
+                MazeFileError::new(*line, width + 1, format!("line is wider than {} columns", width))
+            );
+        }
+        let corner_line = index % 2 == 0;
+        let row = index / 2;
+        for col in 0..width {
+            let c = chars.get(col).copied().unwrap_or(' ');
+            let (expected, wall, valid) = match (corner_line, col % 2 == 0) {
+                (true, true) => ("'+'", false, c == '+'),
+                (true, false) => ("'-' or ' '", c == '-', c == '-' || c == ' '),
+                (false, true) => ("'|' or ' '", c == '|', c == '|' || c == ' '),
+                (false, false) => ("' ' or 'S'", false, c == ' ' || c == 'S'),
+            };
+            if !valid {
+                return Err(
+                    MazeFileError::new(
+                        *line,
+                        col + 1,
+                        format!("unexpected '{}', expected {}", c, expected)
+                    )
+                );
+            }
+            if c == 'S' {
+                spawns.push((row, col / 2));
+            }
+            if !wall {
+                continue;
+            }
+            let code = &mut grid[row][col / 2];
+            *code = if corner_line {
+                if *code == WALL_DOWN { WALL_DOWN_RIGHT } else { WALL_RIGHT }
+            } else if *code == WALL_RIGHT {
+                WALL_DOWN_RIGHT
+            } else {
+                WALL_DOWN
+            };
+        }
+    }
+
+    let mut maze = Maze::from_legacy(name.to_string(), &grid);
+    maze.spawns = spawns;
+    Ok(maze)
+}
+
+/// JSON maze file, either with per-cell walls (`cells`, as serialized by `Maze`)
+/// or with the legacy corner codes (`grid`)
+#[derive(Deserialize)]
+struct JsonMaze {
+    name: String,
+    #[serde(default)]
+    cells: Option<Vec<Vec<CellWalls>>>,
+    #[serde(default)]
+    grid: Option<Vec<Vec<u8>>>,
+    #[serde(default)]
+    spawns: Vec<(usize, usize)>,
+    #[serde(default = "default_cell_size")]
+    cell_size: f32,
+    #[serde(default = "default_offset")]
+    offset: (f32, f32),
+}
+
+fn default_cell_size() -> f32 {
+    CELL_SIZE
+}
+
+fn default_offset() -> (f32, f32) {
+    (MAZE_OFFSET, MAZE_OFFSET)
+}
+
+/// Byte offset of the start of the `index`th item of the JSON array opening at `start`
+fn json_array_item(text: &str, start: usize, index: usize) -> Option<usize> {
+    let (mut depth, mut item, mut in_string, mut escaped) = (0usize, 0usize, false, false);
+    // whether the next non-blank character starts an item
+    let mut waiting = true;
+    for (i, c) in text[start + 1..].char_indices() {
+        if waiting && depth == 0 && !c.is_whitespace() {
+            if c == ']' {
+                return None;
+            }
+            if item == index {
+                return Some(start + 1 + i);
+            }
+            waiting = false;
+        }
+        if in_string {
+            match c {
+                _ if escaped => {
+                    escaped = false;
+                }
+                '\\' => {
+                    escaped = true;
+                }
+                '"' => {
+                    in_string = false;
+                }
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+            }
+            '[' | '{' => {
+                depth += 1;
+            }
+            ']' | '}' if depth == 0 => {
+                return None;
+            }
+            ']' | '}' => {
+                depth -= 1;
+            }
+            ',' if depth == 0 => {
+                item += 1;
+                waiting = true;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// An error located at the value of `key` in a JSON maze file, then at `indexes` in the
+/// nested arrays of that value. The error is at 0:0 when the value can't be found
+fn json_error(text: &str, key: &str, indexes: &[usize], message: String) -> MazeFileError {
+    let locate = || -> Option<usize> {
+        let mut offset = text.find(&format!("\"{}\"", key))?;
+        offset += text[offset..].find(':')? + 1;
+        offset += text[offset..].find(|c: char| !c.is_whitespace())?;
+        for &index in indexes {
+            if !text[offset..].starts_with('[') {
+                return None;
+            }
+            offset = json_array_item(text, offset, index)?;
+        }
+        Some(offset)
+    };
+    match locate() {
+        Some(offset) => {
+            let before = &text[..offset];
+            let line = before.matches('\n').count() + 1;
+            let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+            MazeFileError::new(line, column, message)
+        }
+        None => MazeFileError::new(0, 0, message),
+    }
+}
+
+fn check_rectangle<T>(text: &str, rows: &[Vec<T>], min: usize, key: &str) -> Result<(), MazeFileError> {
+    let width = rows.first().map(|row| row.len()).unwrap_or(0);
+    if rows.len() < min || width < min {
+        let message = format!("{} must be at least {} x {}", key, min, min);
+        return Err(json_error(text, key, &[], message));
+    }
+    for (row, values) in rows.iter().enumerate() {
+        if values.len() != width {
+            let message = format!("{} row {} has {} values instead of {}", key, row, values.len(), width);
+            return Err(json_error(text, key, &[row], message));
+        }
+    }
+    Ok(())
+}
+
+/// Parses a JSON maze file
+pub fn parse_json_maze(text: &str) -> Result<Maze, MazeFileError> {
+    let file: JsonMaze = serde_json
+        ::from_str(text)
+        .map_err(|e| MazeFileError::new(e.line(), e.column(), e.to_string()))?;
+    let cells = match (file.cells, file.grid) {
+        (Some(cells), _) => {
+            check_rectangle(text, &cells, 1, "cells")?;
+            for (row, values) in cells.iter().enumerate() {
+                if let Some(col) = values.iter().position(|walls| walls.bits() > CellWalls::ALL.bits()) {
+                    let message = format!("invalid walls {} at cells[{}][{}]", values[col].bits(), row, col);
+                    return Err(json_error(text, "cells", &[row, col], message));
+                }
+            }
+            cells
+        }
+        (None, Some(grid)) => {
+            check_rectangle(text, &grid, 2, "grid")?;
+            for (row, codes) in grid.iter().enumerate() {
+                let invalid = codes.iter().position(|code| !(WALL_DOWN..=WALL_DOWN_RIGHT).contains(code));
+                if let Some(col) = invalid {
+                    let message = format!("invalid code {} at grid[{}][{}]", codes[col], row, col);
+                    return Err(json_error(text, "grid", &[row, col], message));
+                }
+            }
+            cells_from_legacy(&grid)
+        }
+        (None, None) => {
+            return Err(json_error(text, "name", &[], "missing \"cells\" or \"grid\"".to_string()));
+        }
+    };
+    let mut maze = Maze::new(file.name, cells);
+    let outside = file.spawns.iter().position(|&(row, col)| row >= maze.rows() || col >= maze.cols());
+    if let Some(index) = outside {
+        let (row, col) = file.spawns[index];
+        let message = format!("spawn ({}, {}) is outside of the maze", row, col);
+        return Err(json_error(text, "spawns", &[index], message));
+    }
+    maze.spawns = file.spawns;
+    maze.cell_size = file.cell_size;
+    maze.offset = file.offset;
+    Ok(maze)
+}
+
+/// Loads a maze file, the format is picked from the extension
+pub fn load_maze(path: &Path) -> Result<Maze, MazeFileError> {
+    let text = fs
+        ::read_to_string(path)
+        .map_err(|e| MazeFileError::new(0, 0, format!("cannot read {}: {}", path.display(), e)))?;
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Custom".to_string());
+    if path.extension().is_some_and(|ext| ext == "json") {
+        parse_json_maze(&text)
+    } else {
+        parse_ascii_maze(&name, &text)
+    }
+}
+
+/// Saves a maze file, the format is picked from the extension like in `load_maze`
+pub fn save_maze(path: &Path, maze: &Maze) -> Result<(), MazeFileError> {
+    let text = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::to_string_pretty(maze).map_err(|e| MazeFileError::new(0, 0, e.to_string()))?
+    } else {
+        maze.to_ascii()
+    };
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| {
+            MazeFileError::new(0, 0, format!("cannot create {}: {}", dir.display(), e))
+        })?;
+    }
+    fs::write(path, text).map_err(|e| {
+        MazeFileError::new(0, 0, format!("cannot write {}: {}", path.display(), e))
+    })
+}
+
+/// Loads every maze file found in a directory, sorted by file name.
+/// A missing directory simply means there is no custom level.
+pub fn load_maps_dir(dir: &Path) -> Vec<(PathBuf, Result<Maze, MazeFileError>)> {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) =>
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.is_file())
+                .collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let maze = load_maze(&path);
+            (path, maze)
+        })
+        .collect()
+}
+
+impl Maze {
+    /// Renders the maze in the ASCII art format, `parse_ascii_maze` reads it back
+    pub fn to_ascii(&self) -> String {
+        let mut text = String::new();
+        for row in 0..=self.rows() {
+            let mut corners = String::new();
+            let mut cells = String::new();
+            for col in 0..=self.cols() {
+                corners.push('+');
+                cells.push(if self.has_down(row, col) { '|' } else { ' ' });
+                if col < self.cols() {
+                    corners.push(if self.has_right(row, col) { '-' } else { ' ' });
+                    cells.push(if self.spawns.contains(&(row, col)) { 'S' } else { ' ' });
+                }
+            }
+            text.push_str(corners.trim_end());
+            text.push('\n');
+            if row < self.rows() {
+                text.push_str(cells.trim_end());
+                text.push('\n');
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: &str = "# two rooms\n+-+-+\n|S  |\n+ +-+\n| |\n+-+-+\n";
+
+    #[test]
+    fn ascii_round_trips_through_to_ascii() {
+        let maze = parse_ascii_maze("small", SMALL).unwrap();
+        assert_eq!((maze.rows(), maze.cols()), (2, 2));
+        assert_eq!(maze.spawns, vec![(0, 0)]);
+        assert!(!maze.wall_between((0, 0), (1, 0)));
+        assert!(maze.wall_between((1, 0), (1, 1)));
+
+        let again = parse_ascii_maze("small", &maze.to_ascii()).unwrap();
+        assert_eq!(again, maze);
+        let level = Maze::level(1);
+        let again = parse_ascii_maze(&level.name, &level.to_ascii()).unwrap();
+        assert_eq!(again.cells, level.cells);
+    }
+
+    #[test]
+    fn bad_characters_are_located() {
+        let error = parse_ascii_maze("bad", "# comment\n+-+\n|x|\n+-+\n").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 2));
+        assert!(error.message.contains("'x'"), "{}", error);
+
+        let error = parse_ascii_maze("bad", "+-+\n| |\n+=+\n").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 2));
+    }
+
+    #[test]
+    fn ragged_rows_are_refused() {
+        let error = parse_ascii_maze("wide", "+-+\n| | |\n+-+\n").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 4));
+
+        let error = parse_ascii_maze("open", "+-+\n| |\n").unwrap_err();
+        assert_eq!(error.line, 3);
+
+        let text = "{\n  \"name\": \"ragged\",\n  \"grid\": [[4, 1],\n    [3]]\n}";
+        let error = parse_json_maze(text).unwrap_err();
+        assert_eq!((error.line, error.column), (4, 5));
+    }
+
+    #[test]
+    fn spawns_are_optional_but_inside() {
+        let maze = parse_ascii_maze("empty", "+-+-+\n|   |\n+-+-+\n").unwrap();
+        assert!(maze.spawns.is_empty());
+
+        let text = "{\"name\": \"far\", \"grid\": [[4, 1], [3, 2]],\n\"spawns\": [[0, 0], [5, 0]]}";
+        let error = parse_json_maze(text).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 20));
+    }
+
+    #[test]
+    fn json_takes_cells_or_grid() {
+        let level = Maze::level(2);
+        let maze = parse_json_maze(&serde_json::to_string(&level).unwrap()).unwrap();
+        assert_eq!(maze, level);
+
+        let grid = vec![vec![WALL_DOWN_RIGHT, WALL_RIGHT], vec![WALL_DOWN, WALL_NONE]];
+        let text = format!("{{\"name\": \"grid\", \"grid\": {:?}}}", grid);
+        let maze = parse_json_maze(&text).unwrap();
+        assert_eq!(maze.cells, Maze::from_legacy("grid".to_string(), &grid).cells);
+        assert_eq!(maze.cell_size, CELL_SIZE);
+
+        let error = parse_json_maze("{\"name\": \"none\"}").unwrap_err();
+        assert!(error.message.contains("missing"), "{}", error);
+    }
+
+    #[test]
+    fn saved_mazes_load_back() {
+        let dir = std::env::temp_dir().join(format!("maze-files-{}", std::process::id()));
+        let mut maze = Maze::empty("edited".to_string(), 3, 4);
+        maze.toggle_wall(1, 1, CellWalls::RIGHT);
+        maze.toggle_wall(0, 2, CellWalls::BOTTOM);
+        // the ASCII format lists the spawns in reading order
+        maze.toggle_spawn(0, 0);
+        maze.toggle_spawn(2, 3);
+        for file in ["edited.maze", "edited.json"] {
+            let path = dir.join("nested").join(file);
+            save_maze(&path, &maze).unwrap();
+            assert_eq!(load_maze(&path), Ok(maze.clone()), "{}", file);
+        }
+        let found = load_maps_dir(&dir.join("nested"));
+        assert_eq!(found.len(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(load_maps_dir(&dir).is_empty());
+    }
+
+    #[test]
+    fn maps_dir_is_found_at_run_time() {
+        let exe_dir = std::env::temp_dir().join(format!("maze-exe-{}", std::process::id()));
+        let configured = PathBuf::from("/srv/maze/maps");
+        assert_eq!(resolve_maps_dir(Some(configured.clone()), Some(&exe_dir)), configured);
+        assert_eq!(resolve_maps_dir(None, Some(&exe_dir)), PathBuf::from(MAPS_DIR));
+        assert_eq!(resolve_maps_dir(None, None), PathBuf::from(MAPS_DIR));
+        fs::create_dir_all(exe_dir.join(MAPS_DIR)).unwrap();
+        assert_eq!(resolve_maps_dir(None, Some(&exe_dir)), exe_dir.join(MAPS_DIR));
+        fs::remove_dir_all(&exe_dir).unwrap();
+    }
+
+    #[test]
+    fn shipped_maps_load() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join("server").join(MAPS_DIR);
+        let maps = load_maps_dir(&dir);
+        assert!(maps.iter().any(|(path, _)| path.ends_with("crossroads.maze")));
+        for (path, maze) in maps {
+            assert!(maze.is_ok(), "{}: {:?}", path.display(), maze);
+        }
+    }
+
+    #[test]
+    fn json_errors_are_located() {
+        let error = parse_json_maze("{\n  \"name\": \"bad\",\n  \"grid\": [[4, 1], [3, 9]]\n}").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 24));
+        assert!(error.message.contains("grid[1][1]"), "{}", error);
+
+        let error = parse_json_maze("{\n  \"name\": \"bad\",\n  \"grid\": [[4, 1],").unwrap_err();
+        assert_eq!(error.line, 3);
+    }
+}
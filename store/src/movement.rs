@@ -0,0 +1,99 @@
+use crate::*;
+
+/// Distance between two samples when walking along a move
+const STEP: f32 = 0.05;
+
+/// The cell side crossed when leaving `cell` towards the world position (x, z)
+fn side_towards(maze: &Maze, (row, col): (usize, usize), x: f32, z: f32) -> CellWalls {
+    let (left, top) = maze.corner_position(row, col);
+    if x < left {
+        CellWalls::LEFT
+    } else if x >= left + maze.cell_size {
+        CellWalls::RIGHT
+    } else if z < top {
+        CellWalls::TOP
+    } else {
+        CellWalls::BOTTOM
+    }
+}
+
+/// Whether going straight between two close world positions goes through a wall
+fn blocked(maze: &Maze, from: (f32, f32), to: (f32, f32)) -> bool {
+    let half_arena = ARENA_SIZE / 2.0;
+    if to.0.abs() > half_arena || to.1.abs() > half_arena {
+        return true;
+    }
+    match (maze.cell_at(from.0, from.1), maze.cell_at(to.0, to.1)) {
+        (Some(a), Some(b)) if a == b => false,
+        (Some(a), Some(b)) => {
+            let (ar, ac) = (a.0 as isize, a.1 as isize);
+            let (br, bc) = (b.0 as isize, b.1 as isize);
+            if (ar - br).abs() + (ac - bc).abs() == 1 {
+                maze.wall_between(a, b)
+            } else {
+                // cut through a corner, both ways around it must be blocked to stop the move
+                let via_row = (b.0, a.1);
+                let via_col = (a.0, b.1);
+                (maze.wall_between(a, via_row) || maze.wall_between(via_row, b)) &&
+                    (maze.wall_between(a, via_col) || maze.wall_between(via_col, b))
+            }
+        }
+        (Some(cell), None) => maze.walls(cell.0, cell.1).contains(side_towards(maze, cell, to.0, to.1)),
+        (None, Some(cell)) =>
+            maze.walls(cell.0, cell.1).contains(side_towards(maze, cell, from.0, from.1)),
+        (None, None) => false,
+    }
+}
+
+/// Corrects a move reported by a client: it is shortened to `max_distance` and stopped
+/// in front of the first wall it goes through. Returns `to` when the move is legal.
+pub fn correct_move(maze: &Maze, from: &Position, to: &Position, max_distance: f32) -> Position {
+    let (dx, dz) = (to.x - from.x, to.z - from.z);
+    let distance = (dx * dx + dz * dz).sqrt();
+    let allowed = distance.min(max_distance.max(0.0));
+    let steps = (allowed / STEP).ceil() as usize;
+
+    let mut last = (from.x, from.z);
+    for step in 1..=steps {
+        let travelled = ((step as f32) * STEP).min(allowed);
+        let next = (from.x + (dx * travelled) / distance, from.z + (dz * travelled) / distance);
+        if blocked(maze, last, next) {
+            return Position::new(last.0, to.y, last.1);
+        }
+        last = next;
+    }
+    if allowed < distance {
+        Position::new(last.0, to.y, last.1)
+    } else {
+        to.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walls_stop_moves() {
+        let maze = Maze::level(1);
+        let (a, b) = ((1, 0), (1, 1));
+        assert!(maze.wall_between(a, b));
+        let from = maze.cell_center(a.0, a.1);
+        let to = maze.cell_center(b.0, b.1);
+        let corrected = correct_move(&maze, &from, &to, 10.0);
+        assert_eq!(maze.cell_at(corrected.x, corrected.z), Some(a));
+    }
+
+    #[test]
+    fn legal_moves_are_kept_and_long_ones_clamped() {
+        let maze = Maze::level(1);
+        let (a, b) = ((1, 0), (2, 0));
+        assert!(!maze.wall_between(a, b));
+        let from = maze.cell_center(a.0, a.1);
+        let to = maze.cell_center(b.0, b.1);
+        assert_eq!(correct_move(&maze, &from, &to, 10.0), to);
+
+        let clamped = correct_move(&maze, &from, &to, 0.5);
+        assert!((clamped.z - from.z - 0.5).abs() < 0.001);
+    }
+}
@@ -1,4 +1,6 @@
+use crate::{MAX_NAME_LENGTH, NBR_OF_LIVES};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct Position {
@@ -13,6 +15,15 @@ impl Position {
     }
 }
 
+/// What a player did during the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct Score {
+    pub kills: u32,
+    pub deaths: u32,
+    /// Lives taken from other players
+    pub damage: u32,
+}
+
 /// Struct for storing player related data.
 /// In tic-tac-toe the only thing we need is the name and the piece the player will be placing
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -23,6 +34,8 @@ pub struct Players {
     pub position: Position,
     pub vision: (f32, f32),
     pub lives: u8,
+    #[serde(default)]
+    pub score: Score,
 }
 
 impl Players {
@@ -39,7 +52,87 @@ impl Players {
             position,
             vision,
             client_id,
-            lives: 3,
+            lives: NBR_OF_LIVES,
+            score: Score::default(),
+        }
+    }
+}
+
+/// Why a player name was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameError {
+    Empty,
+    TooLong,
+    ControlCharacter,
+    InvalidUtf8,
+}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameError::Empty => write!(f, "the name is empty"),
+            NameError::TooLong => write!(f, "the name is longer than {} characters", MAX_NAME_LENGTH),
+            NameError::ControlCharacter => write!(f, "the name contains control characters"),
+            NameError::InvalidUtf8 => write!(f, "the name is not valid UTF-8"),
         }
     }
 }
+
+impl std::error::Error for NameError {}
+
+/// Checks a name chosen by a player, surrounding whitespace is removed
+pub fn validate_name(name: &str) -> Result<String, NameError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(NameError::Empty);
+    }
+    if name.chars().any(char::is_control) {
+        return Err(NameError::ControlCharacter);
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(NameError::TooLong);
+    }
+    Ok(name.to_string())
+}
+
+/// Makes `name` different from all the `taken` ones by adding a number to it,
+/// the result still fits in `MAX_NAME_LENGTH`
+pub fn unique_name<'a>(name: &str, taken: impl Iterator<Item = &'a str> + Clone) -> String {
+    let is_taken = |candidate: &str| taken.clone().any(|other| other == candidate);
+    if !is_taken(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|n| {
+            let suffix = format!("_{}", n);
+            let base: String = name
+                .chars()
+                .take(MAX_NAME_LENGTH - suffix.len())
+                .collect();
+            base + &suffix
+        })
+        .find(|candidate| !is_taken(candidate))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_validated() {
+        assert_eq!(validate_name("  neo "), Ok("neo".to_string()));
+        assert_eq!(validate_name("   "), Err(NameError::Empty));
+        assert_eq!(validate_name("ne\no"), Err(NameError::ControlCharacter));
+        assert_eq!(validate_name(&"é".repeat(MAX_NAME_LENGTH)).map(|n| n.chars().count()), Ok(16));
+        assert_eq!(validate_name(&"a".repeat(MAX_NAME_LENGTH + 1)), Err(NameError::TooLong));
+    }
+
+    #[test]
+    fn taken_names_get_a_suffix() {
+        let taken = ["neo", "neo_2", "abcdefghijklmnop"];
+        assert_eq!(unique_name("trinity", taken.iter().copied()), "trinity");
+        assert_eq!(unique_name("neo", taken.iter().copied()), "neo_3");
+        assert_eq!(unique_name("abcdefghijklmnop", taken.iter().copied()), "abcdefghijklmn_2");
+    }
+}
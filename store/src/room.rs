@@ -0,0 +1,25 @@
+use crate::*;
+use serde::{ Deserialize, Serialize };
+
+/// A match hosted by the server, as listed to the clients
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoomInfo {
+    pub id: u32,
+    pub name: String,
+    pub lvl: usize,
+    /// Name of the maze played in the room
+    pub level: String,
+    pub mode: GameMode,
+    pub stage: Stage,
+    pub players: usize,
+    pub max_players: usize,
+    /// Lives of a player when it spawns
+    pub lives: u8,
+}
+
+impl RoomInfo {
+    /// Whether a new player may join the room
+    pub fn is_open(&self) -> bool {
+        self.stage == Stage::PreGame && self.players < self.max_players
+    }
+}
@@ -0,0 +1,118 @@
+use crate::*;
+use serde::{ Deserialize, Serialize };
+
+/// The levels a room plays one after the other, starting over once they were all played
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rotation {
+    pub levels: Vec<usize>,
+    next: usize,
+}
+
+impl Rotation {
+    pub fn new(levels: Vec<usize>) -> Self {
+        Self { levels, next: 0 }
+    }
+
+    /// Every level out of `count`, beginning with the one after `lvl`
+    pub fn after(lvl: usize, count: usize) -> Self {
+        Self::new((0..count).map(|i| ((lvl + i) % count) + 1).collect())
+    }
+
+    /// The level of the next round, None when the rotation is empty
+    pub fn peek(&self) -> Option<usize> {
+        if self.levels.is_empty() {
+            return None;
+        }
+        Some(self.levels[self.next % self.levels.len()])
+    }
+
+    pub fn next_level(&mut self) -> Option<usize> {
+        let lvl = self.peek()?;
+        self.next = (self.next + 1) % self.levels.len();
+        Some(lvl)
+    }
+}
+
+impl GameState {
+    /// Whether the results were shown long enough to begin another round
+    pub fn next_round_due(&self) -> bool {
+        self.stage == Stage::Ended &&
+            self.ended_at.is_some_and(|ended_at| ended_at.elapsed() >= RESULTS_DURATION)
+    }
+
+    /// Goes back to the lobby on another maze. Everyone who took part in the game,
+    /// players and spectators, waits to join the next round
+    pub fn next_round(&mut self, lvl: usize, maze: Maze) {
+        let mut waiting = std::mem::take(&mut self.spectators);
+        for player in self.players.values() {
+            waiting.insert(player.client_id, player.name.clone());
+        }
+        *self = GameState {
+            mode: self.mode,
+            lobby: self.lobby,
+            max_lives: self.max_lives,
+            spectators: waiting,
+            ..GameState::default()
+        };
+        self.set_maze(lvl, maze);
+    }
+
+    /// Seconds left before the next round, for the clients
+    pub fn next_round_in(&self) -> u8 {
+        let shown = self.ended_at.map(|ended_at| ended_at.elapsed()).unwrap_or_default();
+        RESULTS_DURATION.saturating_sub(shown).as_secs() as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn rotation_loops_over_the_levels() {
+        let mut rotation = Rotation::after(2, 3);
+        assert_eq!(rotation.levels, vec![3, 1, 2]);
+        assert_eq!(rotation.peek(), Some(3));
+        let played: Vec<_> = (0..4).filter_map(|_| rotation.next_level()).collect();
+        assert_eq!(played, vec![3, 1, 2, 3]);
+        assert_eq!(Rotation::new(Vec::new()).next_level(), None);
+    }
+
+    #[test]
+    fn next_round_brings_everyone_back_to_the_lobby() {
+        let mut state = GameState {
+            mode: GameMode::deathmatch(),
+            max_lives: 3,
+            ..GameState::default()
+        };
+        for id in 0..2u8 {
+            let event = GameEvent::PlayerJoined {
+                player_id: id,
+                name: format!("p{}", id),
+                position: state.random_spawn(),
+                client_id: id as u64,
+            };
+            state.consume(&event, id as u64);
+        }
+        state.add_spectator(7, "late".to_string());
+        state.consume(&GameEvent::BeginGame { player_list: state.players.clone() }, u64::MAX);
+        let reason = EndGameReason::TimeLimit { winner: None };
+        state.consume(&GameEvent::EndGame { reason }, u64::MAX);
+        assert_eq!(state.stage, Stage::Ended);
+        assert!(!state.next_round_due());
+        state.ended_at = Some(Instant::now() - RESULTS_DURATION);
+        assert!(state.next_round_due());
+
+        state.next_round(2, Maze::level(2));
+        assert_eq!((state.stage, state.lvl), (Stage::PreGame, 2));
+        assert_eq!((state.mode, state.max_lives), (GameMode::deathmatch(), 3));
+        assert!(state.players.is_empty());
+        let waiting: Vec<u64> = state
+            .take_spectators()
+            .into_iter()
+            .map(|(client_id, _)| client_id)
+            .collect();
+        assert_eq!(waiting, vec![0, 1, 7]);
+    }
+}
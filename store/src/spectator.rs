@@ -0,0 +1,54 @@
+use crate::*;
+
+impl GameState {
+    pub fn is_spectator(&self, client_id: u64) -> bool {
+        self.spectators.contains_key(&client_id)
+    }
+
+    /// Lets a client watch the game in progress until the next round
+    pub fn add_spectator(&mut self, client_id: u64, name: String) {
+        self.spectators.insert(client_id, name);
+    }
+
+    /// Empties the spectators, oldest client first, so that they can join the next round
+    pub fn take_spectators(&mut self) -> Vec<(u64, String)> {
+        let mut spectators: Vec<(u64, String)> = self.spectators.drain().collect();
+        spectators.sort_by_key(|(client_id, _)| *client_id);
+        spectators
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn spectators_only_watch() {
+        let mut state = GameState::default();
+        let event = GameEvent::PlayerJoined {
+            player_id: 0,
+            name: "p0".to_string(),
+            position: state.random_spawn(),
+            client_id: 0,
+        };
+        state.consume(&event, 0);
+        state.consume(&GameEvent::BeginGame { player_list: HashMap::new() }, u64::MAX);
+        state.add_spectator(7, "late".to_string());
+        assert!(state.is_spectator(7));
+
+        let fire = GameEvent::Fire {
+            origin: state.players[&0].position.clone(),
+            direction: Position::new(0.0, 0.0, 1.0),
+            timestamp: now_millis(),
+        };
+        assert!(!state.validate(&fire, 7));
+        assert!(!state.validate(&GameEvent::Ready { ready: true }, 7));
+
+        // a player knocked out of a last man standing game watches the rest of it
+        state.consume(&state.death_of(0), u64::MAX);
+        assert!(state.is_spectator(0));
+        assert_eq!(state.take_spectators(), vec![(0, "p0".to_string()), (7, "late".to_string())]);
+        assert!(state.spectators.is_empty());
+    }
+}